@@ -0,0 +1,69 @@
+use std::error::Error;
+
+use ark_bn254::Fr;
+use ark_r1cs_std::{alloc::AllocVar, R1CSVar};
+use ark_relations::r1cs::ConstraintSystem;
+use num::{bigint::RandBigInt, BigUint, One};
+use rand::thread_rng;
+
+use super::{pow2_bound, BigUintVar, Reducer, ReductionStrategy};
+
+const W: usize = 32;
+const N: usize = crate::TEST_BITS;
+
+// The constraints of x * y mod n^2 for x, y < n^2, fully reduced, i.e., including the setup
+// of the reducer, the conversions in and out of Montgomery form and the final range check
+// against n^2. The lower bound on n^2 is the one `PaillierEncCircuit` passes.
+fn reduction_cost(strategy: ReductionStrategy) -> Result<usize, Box<dyn Error>> {
+    let rng = &mut thread_rng();
+    // n must be odd for Montgomery.
+    let n = rng.gen_biguint_range(
+        &((BigUint::one() << N) - (BigUint::one() << (N - 2))),
+        &(BigUint::one() << N),
+    ) | BigUint::one();
+    let nn = &n * &n;
+    let x = rng.gen_biguint_below(&nn);
+    let y = rng.gen_biguint_below(&nn);
+
+    let cs = ConstraintSystem::<Fr>::new_ref();
+    let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+    let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N * 2)))?;
+    let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), N * 2)))?;
+    let before = cs.num_constraints();
+    let reducer = Reducer::new(&nn_var, &pow2_bound(N * 2), strategy)?;
+    let r = x_var.mul_mod_with(&y_var, &reducer)?;
+    r.enforce_lt(&nn_var)?;
+    let count = cs.num_constraints() - before;
+    assert_eq!(r.value()?, &x * &y % &nn, "{:?}", strategy);
+    assert!(cs.is_satisfied()?, "{:?}", strategy);
+    Ok(count)
+}
+
+#[test]
+fn bench_reductions() -> Result<(), Box<dyn Error>> {
+    let strategies = [
+        ReductionStrategy::Schoolbook,
+        ReductionStrategy::WidthBound,
+        ReductionStrategy::Montgomery,
+    ];
+    let mut counts = vec![];
+    for strategy in strategies {
+        let count = reduction_cost(strategy)?;
+        // The count only depends on the sizes, so it is the same for every modulus and inputs.
+        assert_eq!(reduction_cost(strategy)?, count, "{:?}", strategy);
+        counts.push(count);
+    }
+
+    println!("{:<12} {:>12}", "strategy", "constraints");
+    for (strategy, count) in strategies.iter().zip(&counts) {
+        println!("{:<12} {:>12}", format!("{:?}", strategy), count);
+    }
+
+    // On a CPU, Montgomery <= Barrett <= schoolbook. In R1CS, every strategy witnesses the
+    // quotient, so they only differ in its width and in the steps around it. The bound 2^(2N)
+    // passed for n^2 is above the width bound 2^(2N - 2), which makes the quotient narrower,
+    // and Montgomery adds R^2 mod n^2, the conversions and `reduce_once`, so the ordering is
+    // the reverse.
+    assert!(counts[0] <= counts[1] && counts[1] <= counts[2], "{:?}", counts);
+    Ok(())
+}
@@ -0,0 +1,102 @@
+use ark_bn254::Fr;
+use ark_r1cs_std::{alloc::AllocVar, R1CSVar, ToBitsGadget};
+use ark_relations::r1cs::{ConstraintSystem, ConstraintSystemRef};
+use num::{BigInt, BigUint, Integer, One, Zero};
+use proptest::prelude::*;
+
+use super::BigUintVar;
+use crate::paillier::group::{ModularGroup, PaillierGroup};
+
+// Each property runs a gadget and its `num` counterpart on random operands, and checks that
+// the values agree and the system is satisfied. The operands are built from byte vectors, so
+// that failures shrink towards short, i.e., small, operands, down to zero. There is no
+// quotient-returning `div_rem` gadget; `rem` covers the division.
+
+const W: usize = 32;
+const BITS: usize = 256;
+
+fn biguint(max_bytes: usize) -> impl Strategy<Value = BigUint> {
+    prop::collection::vec(any::<u8>(), 0..=max_bytes)
+        .prop_map(|bytes| BigUint::from_bytes_le(&bytes))
+}
+
+// Two operands, which are equal for about half of the cases.
+fn operands() -> impl Strategy<Value = (BigUint, BigUint)> {
+    (biguint(BITS / 8), biguint(BITS / 8), any::<bool>())
+        .prop_map(|(x, y, same)| (x.clone(), if same { x } else { y }))
+}
+
+fn alloc(cs: &ConstraintSystemRef<Fr>, x: &BigUint, bits: usize) -> BigUintVar<Fr, W> {
+    BigUintVar::new_witness(cs.clone(), || Ok((x.clone(), bits))).unwrap()
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn fuzz_add((x, y) in operands()) {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let z = alloc(&cs, &x, BITS).add_no_carry(&alloc(&cs, &y, BITS)).align().unwrap();
+        prop_assert_eq!(z.value().unwrap(), &x + &y);
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn fuzz_sub((x, y) in operands()) {
+        let (x, y) = if x >= y { (x, y) } else { (y, x) };
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let z = alloc(&cs, &x, BITS).sub_with_bits(&alloc(&cs, &y, BITS), BITS).unwrap();
+        prop_assert_eq!(z.value().unwrap(), &x - &y);
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn fuzz_mul_no_carry((x, y) in operands()) {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let z = alloc(&cs, &x, BITS).mul_no_carry(&alloc(&cs, &y, BITS)).unwrap();
+        prop_assert_eq!(z.value().unwrap(), &x * &y);
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn fuzz_rem((x, y) in operands(), m in biguint(BITS / 8)) {
+        prop_assume!(!m.is_zero());
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = alloc(&cs, &m, BITS);
+        let z = alloc(&cs, &x, BITS)
+            .mul_no_carry(&alloc(&cs, &y, BITS))
+            .unwrap()
+            .rem_reduced(&m_var, &BigUint::one())
+            .unwrap();
+        prop_assert_eq!(z.value().unwrap(), &x * &y % &m);
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn fuzz_modinv(x in biguint(BITS / 8), m in biguint(BITS / 8)) {
+        prop_assume!(m > BigUint::one());
+        let x = x % &m;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let group = PaillierGroup::new(alloc(&cs, &m, BITS), BigUint::one());
+        let y = group.inverse(&alloc(&cs, &x, BITS)).unwrap();
+        // The inverse exists iff x is coprime to m, otherwise the system is unsatisfiable.
+        let gcd = BigInt::from(x.clone()).extended_gcd(&BigInt::from(m.clone()));
+        if gcd.gcd.is_one() {
+            prop_assert_eq!(y.value().unwrap(), gcd.x.mod_floor(&BigInt::from(m)).to_biguint().unwrap());
+            prop_assert!(cs.is_satisfied().unwrap());
+        } else {
+            prop_assert!(!cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn fuzz_powm(x in biguint(BITS / 8), e in biguint(8), m in biguint(BITS / 8)) {
+        prop_assume!(!m.is_zero());
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = alloc(&cs, &m, BITS);
+        let e_bits = alloc(&cs, &e, 64).to_bits_le().unwrap();
+        let z = alloc(&cs, &x, BITS).powm(&e_bits, &m_var, &BigUint::one()).unwrap();
+        prop_assert_eq!(z.value().unwrap(), x.modpow(&e, &m));
+        prop_assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -1,6 +1,8 @@
 use std::{
     borrow::Borrow,
     cmp::{max, min},
+    fmt,
+    rc::Rc,
 };
 
 use ark_ff::{BigInteger, One, PrimeField};
@@ -12,8 +14,163 @@ use ark_r1cs_std::{
     select::CondSelectGadget,
     R1CSVar, ToBitsGadget,
 };
-use ark_relations::r1cs::{ConstraintSystemRef, Namespace, SynthesisError};
-use num::{integer::ExtendedGcd, BigInt, BigUint, Integer, Signed, Zero};
+use ark_relations::{
+    lc, ns,
+    r1cs::{ConstraintSystemRef, Namespace, SynthesisError, Variable},
+};
+use num::{bigint::RandBigInt, integer::ExtendedGcd, BigInt, BigUint, Integer, Signed, Zero};
+use rand::{CryptoRng, RngCore};
+
+pub mod ops;
+pub mod radix;
+
+#[cfg(test)]
+mod bench;
+#[cfg(all(test, feature = "proptest"))]
+mod fuzz;
+
+// The bound 2^bits, e.g., the lower bound on a modulus with `bits + 1` bits.
+pub fn pow2_bound(bits: usize) -> BigUint {
+    BigUint::one() << bits
+}
+
+// The odd primes `enforce_modulus_sane` rules out as factors.
+const SMALL_PRIMES: [u32; 14] = [3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+
+// Makes the next `rem` produce a wrong quotient hint, to test `debug-check`.
+#[cfg(all(test, feature = "debug-check"))]
+thread_local! {
+    static CORRUPT_QUOTIENT: std::cell::Cell<bool> = std::cell::Cell::new(false);
+}
+
+#[cfg(debug_assertions)]
+thread_local! {
+    static POWM_TRACE: std::cell::Cell<bool> = std::cell::Cell::new(false);
+}
+
+// Runs f with every `powm` checking itself after each window: the intermediate result has to
+// match the native power for the exponent bits processed so far, and the constraint system
+// has to be still satisfied if it was before the call. Otherwise, it panics with the index of
+// the window. This is slow, as the whole system is checked once per window.
+#[cfg(debug_assertions)]
+pub fn with_powm_trace<R>(f: impl FnOnce() -> R) -> R {
+    // Restores the previous mode even if f panics.
+    struct Reset(bool);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            POWM_TRACE.with(|t| t.set(self.0));
+        }
+    }
+    let _reset = Reset(POWM_TRACE.with(|t| t.replace(true)));
+    f()
+}
+
+// The limb count from which `mul_dispatch` uses Karatsuba. `mul_no_carry` costs one
+// constraint per output limb, i.e., 2L - 1 for L limbs, while one level of Karatsuba takes
+// three products of L / 2 limbs, i.e., about 3L. So Karatsuba never wins in R1CS, and the
+// default disables it. `with_karatsuba_threshold` overrides it for experiments.
+pub const KARATSUBA_THRESHOLD: usize = usize::MAX;
+
+thread_local! {
+    static KARATSUBA_LIMBS: std::cell::Cell<usize> = std::cell::Cell::new(KARATSUBA_THRESHOLD);
+}
+
+// Runs f with `mul_dispatch` using Karatsuba for operands of at least `limbs` limbs.
+pub fn with_karatsuba_threshold<R>(limbs: usize, f: impl FnOnce() -> R) -> R {
+    struct Reset(usize);
+    impl Drop for Reset {
+        fn drop(&mut self) {
+            KARATSUBA_LIMBS.with(|t| t.set(self.0));
+        }
+    }
+    let _reset = Reset(KARATSUBA_LIMBS.with(|t| t.replace(limbs)));
+    f()
+}
+
+// How the exponentiations and modular products reduce modulo m. The constraint counts differ
+// with the sizes involved, see `bench_reductions`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ReductionStrategy {
+    // `rem` with the public lower bound on m given by the caller.
+    #[default]
+    Schoolbook,
+    // `rem` with the lower bound implied by the declared width of m, as in `rem_by_width`.
+    // Unsatisfiable for a modulus more than two bits shorter than its width. This is what
+    // Barrett reduction comes down to in R1CS, where the quotient is a witness anyway and a
+    // precomputed reciprocal would only add constraints.
+    WidthBound,
+    // `mont_mul` followed by `reduce_once`, with the intermediate results in Montgomery form.
+    // m has to be odd, and the bases have to fit in as many limbs as m.
+    Montgomery,
+}
+
+// Reduces modulo m as in a given strategy. Values are in the strategy's form between `enter`
+// and `leave`, which only differs from the plain one for Montgomery, where x is kept as
+// x * R mod m for R = 2^(W * m.0.len()). A circuit builds one per modulus and passes it to
+// every `*_with` gadget, so that the setup of the strategy is only paid once.
+pub struct Reducer<'a, F: PrimeField, const W: usize> {
+    m: &'a BigUintVar<F, W>,
+    m_lbound: BigUint,
+    strategy: ReductionStrategy,
+    // R^2 mod m for Montgomery, computed once per reducer.
+    rr: Option<BigUintVar<F, W>>,
+}
+
+impl<'a, F: PrimeField, const W: usize> Reducer<'a, F, W> {
+    pub fn new(
+        m: &'a BigUintVar<F, W>,
+        m_lbound: &BigUint,
+        strategy: ReductionStrategy,
+    ) -> Result<Self, SynthesisError> {
+        let (m_lbound, rr) = match strategy {
+            ReductionStrategy::Schoolbook => (m_lbound.clone(), None),
+            ReductionStrategy::WidthBound => (m.width_lbound()?, None),
+            ReductionStrategy::Montgomery => {
+                // R^2 / m can exceed what `m_lbound` allows for a product of two values below
+                // m, so this takes the wider quotient of `rem_auto`, but only once.
+                let rr = pow2_bound(W * m.0.len() * 2);
+                let rr = BigUintVar::constant(rr.clone(), rr.bits() as usize)?.rem_auto(m)?;
+                (m_lbound.clone(), Some(rr))
+            }
+        };
+        Ok(Self { m, m_lbound, strategy, rr })
+    }
+
+    pub fn modulus(&self) -> &'a BigUintVar<F, W> {
+        self.m
+    }
+
+    // For inputs below m, `mont_mul` returns less than m * m / R + m < 2m, which is what
+    // `reduce_once` needs.
+    fn mul(
+        &self,
+        x: &BigUintVar<F, W>,
+        y: &BigUintVar<F, W>,
+    ) -> Result<BigUintVar<F, W>, SynthesisError> {
+        match self.strategy {
+            ReductionStrategy::Montgomery => x.mont_mul(y, self.m)?.reduce_once(self.m),
+            _ => x.mul_no_carry(y)?.rem(self.m, &self.m_lbound),
+        }
+    }
+
+    fn one(&self) -> Result<BigUintVar<F, W>, SynthesisError> {
+        self.enter(&BigUintVar::constant(BigUint::one(), self.m.ubound().bits() as usize)?)
+    }
+
+    fn enter(&self, x: &BigUintVar<F, W>) -> Result<BigUintVar<F, W>, SynthesisError> {
+        match &self.rr {
+            Some(rr) => self.mul(x, rr),
+            None => Ok(x.clone()),
+        }
+    }
+
+    fn leave(&self, x: &BigUintVar<F, W>) -> Result<BigUintVar<F, W>, SynthesisError> {
+        match &self.rr {
+            Some(_) => self.mul(x, &BigUintVar::constant(BigUint::one(), 1)?),
+            None => Ok(x.clone()),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct BitsVar<F: PrimeField, const W: usize>(pub FpVar<F>, pub BigUint);
@@ -111,7 +268,7 @@ impl<F: PrimeField, const W: usize> BitsVar<F, W> {
 }
 
 #[derive(Clone)]
-pub struct BigUintVar<F: PrimeField, const W: usize>(pub Vec<BitsVar<F, W>>);
+pub struct BigUintVar<F: PrimeField, const W: usize>(pub Rc<[BitsVar<F, W>]>);
 
 impl<F: PrimeField, const W: usize> AllocVar<(BigUint, usize), F> for BigUintVar<F, W> {
     fn new_variable<T: Borrow<(BigUint, usize)>>(
@@ -119,7 +276,9 @@ impl<F: PrimeField, const W: usize> AllocVar<(BigUint, usize), F> for BigUintVar
         f: impl FnOnce() -> Result<T, SynthesisError>,
         mode: AllocationMode,
     ) -> Result<Self, SynthesisError> {
-        let cs = cs.into().cs();
+        // Keep the namespace alive, so that the range checks below are traced under it.
+        let ns = cs.into();
+        let cs = ns.cs();
         let v = f()?;
         let (x, l) = v.borrow();
 
@@ -127,15 +286,96 @@ impl<F: PrimeField, const W: usize> AllocVar<(BigUint, usize), F> for BigUintVar
         for chunk in (0..*l).map(|i| x.bit(i as u64)).collect::<Vec<_>>().chunks(W) {
             let limb = F::from_bigint(F::BigInt::from_bits_le(chunk)).unwrap();
             let limb = FpVar::new_variable(cs.clone(), || Ok(limb), mode)?;
+            // Public inputs are range-checked too, so their limbs have to be the canonical
+            // ones of a value below 2^l, which are what `inputize` produces. Other encodings
+            // of the same integer, e.g., with a limb of 2^W or more, are rejected.
             Self::to_bit_array(&limb, chunk.len())?;
             limbs.push(BitsVar(limb, (BigUint::one() << chunk.len()) - BigUint::one()));
         }
 
-        Ok(Self(limbs))
+        let z = Self(limbs.into());
+        debug_assert_eq!(z.num_limbs(), z.declared_bits().div_ceil(W));
+        Ok(z)
     }
 }
 
 impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
+    // Same as `new_witness`, but packs as many limbs as fit below the field size into one
+    // element and range-checks them with a single decomposition. The limbs are then linear
+    // combinations of the decomposed bits. This saves one constraint per limb but the first
+    // of each pack.
+    pub fn new_witness_packed(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<(BigUint, usize), SynthesisError>,
+    ) -> Result<Self, SynthesisError> {
+        Self::new_variable_packed(cs, f, AllocationMode::Witness)
+    }
+
+    // Same as `new_witness_packed` for public inputs, which the verifier encodes with
+    // `inputize_packed`. Besides the constraints, this saves the verifier the scalar
+    // multiplications for all limbs but the first of each pack.
+    pub fn new_input_packed(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<(BigUint, usize), SynthesisError>,
+    ) -> Result<Self, SynthesisError> {
+        Self::new_variable_packed(cs, f, AllocationMode::Input)
+    }
+
+    // The number of W-bit limbs packed into one field element.
+    fn limbs_per_pack() -> usize {
+        (F::MODULUS_BIT_SIZE as usize - 1) / W
+    }
+
+    fn new_variable_packed(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<(BigUint, usize), SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let (x, l) = f()?;
+
+        let mut limbs = vec![];
+        for chunk in
+            (0..l).map(|i| x.bit(i as u64)).collect::<Vec<_>>().chunks(W * Self::limbs_per_pack())
+        {
+            let packed = F::from_bigint(F::BigInt::from_bits_le(chunk)).unwrap();
+            let packed = FpVar::new_variable(cs.clone(), || Ok(packed), mode)?;
+            limbs.extend(Self::to_bit_array(&packed, chunk.len())?.chunks(W).map(BitsVar::from));
+        }
+
+        Ok(Self(limbs.into()))
+    }
+
+    // The declared width `l`, lowered by one limb if the top limb of x is zero.
+    fn trimmed_width(x: &BigUint, l: usize) -> usize {
+        let top = l.div_ceil(W).saturating_sub(1) * W;
+        if x.bits() as usize <= top {
+            top
+        } else {
+            l
+        }
+    }
+
+    // Same as `new_input`, but drops the top limb if it is zero for the supplied value, which
+    // is then public anyway. The layout depends on the value, so the setup has to use a value
+    // of the same limb count, and the verifier has to encode it with `inputize_trimmed`.
+    // Witnesses are not trimmed, as their layout would leak a bound on the secret value.
+    pub fn new_input_trimmed(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<(BigUint, usize), SynthesisError>,
+    ) -> Result<Self, SynthesisError> {
+        let (x, l) = f()?;
+        let l = Self::trimmed_width(&x, l);
+        Self::new_input(cs, || Ok((x, l)))
+    }
+
+    pub fn inputize_trimmed(x: &BigUint, l: usize) -> Vec<F> {
+        Self::inputize(x, Self::trimmed_width(x, l))
+    }
+
+    // The canonical limbs of x mod 2^l. Callers have to check that x fits in l bits, as a
+    // larger x is silently verified as x mod 2^l.
     pub fn inputize(x: &BigUint, l: usize) -> Vec<F> {
         (0..l)
             .map(|i| x.bit(i as u64))
@@ -144,6 +384,19 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
             .map(|chunk| F::from_bigint(F::BigInt::from_bits_le(chunk)).unwrap())
             .collect()
     }
+
+    // The encoding of x mod 2^l for `new_input_packed`.
+    pub fn inputize_packed(x: &BigUint, l: usize) -> Vec<F> {
+        Self::inputize(x, l)
+            .chunks(Self::limbs_per_pack())
+            .map(|chunk| {
+                chunk
+                    .iter()
+                    .rev()
+                    .fold(F::zero(), |acc, limb| acc * F::from(BigUint::one() << W) + limb)
+            })
+            .collect()
+    }
 }
 
 impl<F: PrimeField, const W: usize> R1CSVar<F> for BigUintVar<F, W> {
@@ -165,6 +418,68 @@ impl<F: PrimeField, const W: usize> R1CSVar<F> for BigUintVar<F, W> {
     }
 }
 
+// K integers of the same declared width, e.g., a batch of messages or ciphertexts.
+#[derive(Clone, Debug)]
+pub struct BigUintVarArray<F: PrimeField, const W: usize, const K: usize>(
+    pub [BigUintVar<F, W>; K],
+);
+
+impl<F: PrimeField, const W: usize, const K: usize> AllocVar<([BigUint; K], usize), F>
+    for BigUintVarArray<F, W, K>
+{
+    fn new_variable<T: Borrow<([BigUint; K], usize)>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        let v = f()?;
+        let (xs, l) = v.borrow();
+
+        let vars = xs
+            .iter()
+            .map(|x| BigUintVar::new_variable(cs.clone(), || Ok((x.clone(), *l)), mode))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self(vars.try_into().unwrap()))
+    }
+}
+
+impl<F: PrimeField, const W: usize, const K: usize> R1CSVar<F> for BigUintVarArray<F, W, K> {
+    type Value = [BigUint; K];
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.0[..].cs()
+    }
+
+    fn value(&self) -> Result<Self::Value, SynthesisError> {
+        Ok(self.0.iter().map(|x| x.value()).collect::<Result<Vec<_>, _>>()?.try_into().unwrap())
+    }
+}
+
+impl<F: PrimeField, const W: usize> fmt::Debug for BigUintVar<F, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut s = f.debug_struct("BigUintVar");
+        if let Ok(v) = self.value() {
+            s.field("value", &format_args!("{:#x}", v));
+        }
+        let limb_bound = self.0.iter().map(|limb| &limb.1).max().cloned().unwrap_or_default();
+        s.field("limbs", &self.0.len())
+            .field("bits", &self.ubound().bits())
+            .field("limb_bound", &format_args!("{:#x}", limb_bound))
+            .finish()
+    }
+}
+
+impl<F: PrimeField, const W: usize> fmt::Display for BigUintVar<F, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.value() {
+            Ok(v) => write!(f, "{:#x}", v),
+            Err(_) => write!(f, "<unassigned {}-bit value>", self.ubound().bits()),
+        }
+    }
+}
+
 impl<F: PrimeField, const W: usize> EqGadget<F> for BigUintVar<F, W> {
     fn is_eq(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
         let n = min(self.0.len(), other.0.len());
@@ -198,51 +513,163 @@ impl<F: PrimeField, const W: usize> EqGadget<F> for BigUintVar<F, W> {
 }
 
 impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
-    pub fn enforce_lt(&self, other: &Self) -> Result<(), SynthesisError> {
-        let len = max(self.0.len(), other.0.len());
-        let zero = BitsVar::zero();
+    // `is_eq` and `is_lt` compare limb by limb, which is only meaningful for normalized limbs.
+    // Unlike `align`, this also splits the final carry, so that every limb fits in W bits.
+    fn normalized(&self) -> Result<Self, SynthesisError> {
+        if self.0.iter().all(|limb| limb.1.bits() as usize <= W) {
+            return Ok(self.clone());
+        }
+        let len = (self.ubound().bits() as usize).div_ceil(W);
+        let mut limbs = self.0.to_vec();
+        limbs.resize(max(len, limbs.len()), BitsVar::zero());
+        // The limbs beyond `len` have a zero upper bound, so they are the constant zero.
+        Ok(Self(Self(limbs.into()).align()?.0[..len].into()))
+    }
+
+    // Re-expresses both operands with W-bit limbs and the same number of limbs, so that they
+    // represent the same integer iff they are equal limb by limb.
+    pub fn align_with(&self, other: &Self) -> Result<(Self, Self), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "align_with");
+        let (x, y) = (self.normalized()?, other.normalized()?);
+        let len = max(x.0.len(), y.0.len());
+        let pad = |z: Self| {
+            let mut limbs = z.0.to_vec();
+            limbs.resize(len, BitsVar::zero());
+            Self(limbs.into())
+        };
+        Ok((pad(x), pad(y)))
+    }
+
+    pub fn is_lt(&self, other: &Self) -> Result<Boolean<F>, SynthesisError> {
+        let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "is_lt");
+        let (x, y) = self.align_with(other)?;
+        let len = x.0.len();
 
         let mut delta = vec![];
         for i in 0..len {
-            delta.push(&other.0.get(i).unwrap_or(&zero).0 - &self.0.get(i).unwrap_or(&zero).0);
+            delta.push(&y.0[i].0 - &x.0[i].0);
         }
 
-        let helper = {
-            let cs = self.cs().or(other.cs());
+        // helper[i] marks the most significant limb where x and y differ, and `equal` is set
+        // if there is no such limb.
+        let (helper, equal, lt) = {
             let mut helper = vec![false; len];
             for i in (0..len).rev() {
-                let x = self.0.get(i).unwrap_or(&zero).value().unwrap_or_default();
-                let y = other.0.get(i).unwrap_or(&zero).value().unwrap_or_default();
-                if y > x {
+                let xi = x.0[i].value().unwrap_or_default();
+                let yi = y.0[i].value().unwrap_or_default();
+                if xi != yi {
                     helper[i] = true;
                     break;
                 }
             }
+            let equal = !helper.contains(&true);
+            let lt = x.value().unwrap_or_default() < y.value().unwrap_or_default();
             if cs.is_none() {
-                Vec::<Boolean<F>>::new_constant(cs, helper)?
+                (
+                    Vec::<Boolean<F>>::new_constant(cs.clone(), helper)?,
+                    Boolean::constant(equal),
+                    Boolean::constant(lt),
+                )
             } else {
-                Vec::new_witness(cs, || Ok(helper))?
+                (
+                    Vec::new_witness(cs.clone(), || Ok(helper))?,
+                    Boolean::new_witness(cs.clone(), || Ok(equal))?,
+                    Boolean::new_witness(cs.clone(), || Ok(lt))?,
+                )
             }
         };
 
         let mut c = FpVar::<F>::zero();
-        let mut r = FpVar::zero();
+        let mut r = FpVar::from(equal.clone());
         for (b, d) in helper.into_iter().zip(delta) {
             c += b.select(&d, &FpVar::zero())?;
             (&r * &d).enforce_equal(&FpVar::zero())?;
             r += FpVar::from(b);
         }
-        Self::to_bit_array(&(c - FpVar::one()), W)?;
         r.enforce_equal(&FpVar::one())?;
 
+        // The marked difference c lies in (-2^W, 2^W) and is positive iff x < y. When x == y,
+        // c is zero, the range check is trivially met, and `lt` must be false.
+        let t = lt.select(&(&c - FpVar::one()), &(FpVar::zero() - &c - FpVar::one()))?;
+        Self::to_bit_array(&(t + FpVar::from(equal.clone())), W)?;
+        lt.and(&equal)?.enforce_equal(&Boolean::FALSE)?;
+
+        Ok(lt)
+    }
+
+    pub fn enforce_lt(&self, other: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "enforce_lt");
+        self.is_lt(other)?.enforce_equal(&Boolean::TRUE)
+    }
+
+    pub fn enforce_neq(&self, other: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "enforce_neq");
+        let (x, y) = self.align_with(other)?;
+        x.is_eq(&y)?.enforce_equal(&Boolean::FALSE)
+    }
+
+    // The normalized limbs of self packed as in `new_input_packed`, and c encoded the same way,
+    // or None if c exceeds the upper bound of self. The packs are below 2^(W * k) < |F|, so
+    // they are equal iff the integers are.
+    fn packs_against_constant(
+        &self,
+        c: &BigUint,
+    ) -> Result<Option<(Vec<FpVar<F>>, Vec<F>)>, SynthesisError> {
+        if c > &self.ubound() {
+            return Ok(None);
+        }
+        let x = self.normalized()?;
+        let packs =
+            x.0.chunks(Self::limbs_per_pack())
+                .map(|chunk| {
+                    chunk.iter().rev().fold(FpVar::zero(), |acc, limb| {
+                        acc * F::from(BigUint::one() << W) + &limb.0
+                    })
+                })
+                .collect();
+        Ok(Some((packs, Self::inputize_packed(c, x.0.len() * W))))
+    }
+
+    // Same as `is_eq` with a constant, but the limbs of c only appear as coefficients, and
+    // several limbs are compared at once.
+    pub fn is_eq_constant(&self, c: &BigUint) -> Result<Boolean<F>, SynthesisError> {
+        let _ns = ns!(self.cs(), "is_eq_constant");
+        let Some((packs, expected)) = self.packs_against_constant(c)? else {
+            return Ok(Boolean::FALSE);
+        };
+        let mut r = Boolean::TRUE;
+        for (x, c) in packs.iter().zip(expected) {
+            r = r.and(&x.is_eq(&FpVar::constant(c))?)?;
+        }
+        Ok(r)
+    }
+
+    // A c above the upper bound of self still yields a circuit, which is then unsatisfiable,
+    // so that setup does not depend on whether the constant is reachable.
+    pub fn enforce_eq_constant(&self, c: &BigUint) -> Result<(), SynthesisError> {
+        let cs = self.cs();
+        let _ns = ns!(cs, "enforce_eq_constant");
+        let Some((packs, expected)) = self.packs_against_constant(c)? else {
+            if cs.is_none() {
+                return Err(SynthesisError::Unsatisfiable);
+            }
+            // 0 * 0 = 1
+            return cs.enforce_constraint(lc!(), lc!(), lc!() + Variable::One);
+        };
+        for (x, c) in packs.iter().zip(expected) {
+            x.enforce_equal(&FpVar::constant(c))?;
+        }
         Ok(())
     }
 
     pub fn enforce_equal_unaligned(&self, other: &Self) -> Result<(), SynthesisError> {
         let cs = self.cs().or(other.cs());
-        let len = min(self.0.len(), other.0.len());
+        let _ns = ns!(cs, "enforce_equal_unaligned");
+        let len = max(self.0.len(), other.0.len());
+        let zero = BitsVar::zero();
 
-        let (steps, xxs, yys, rest) = {
+        let (steps, xxs, yys) = {
             let mut steps = vec![];
             let mut x_grouped = vec![];
             let mut y_grouped = vec![];
@@ -255,8 +682,8 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
                     let delta = BigUint::one() << (W * (j - i));
                     let delta = BitsVar(FpVar::Constant(F::from(delta.clone())), delta);
                     match (
-                        self.0[j].mul(&delta).and_then(|x| xx.add(&x)),
-                        other.0[j].mul(&delta).and_then(|y| yy.add(&y)),
+                        self.0.get(j).unwrap_or(&zero).mul(&delta).and_then(|x| xx.add(&x)),
+                        other.0.get(j).unwrap_or(&zero).mul(&delta).and_then(|y| yy.add(&y)),
                     ) {
                         (Some(x), Some(y)) => (xx, yy) = (x, y),
                         _ => break,
@@ -268,17 +695,23 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
                 y_grouped.push(yy);
                 i = j;
             }
-            let mut vv = BitsVar::zero();
-            for v in &(if i < self.0.len() { self } else { other }).0[i..] {
-                vv = vv.add(v).unwrap();
-            }
-            (steps, x_grouped, y_grouped, vv.0)
+            (steps, x_grouped, y_grouped)
         };
         let n = steps.len();
         let mut acc = BigUint::zero();
         let mut c = FpVar::<F>::zero();
+        let mut carrying = false;
         for i in 0..n {
             let step = steps[i];
+            // While no carry has been produced yet, a group whose limbs on both sides are
+            // already normalized fits in `step` bits, so the packed values can be compared
+            // directly without the offset and the carry decomposition.
+            let normalized = BigUint::one() << step;
+            if !carrying && xxs[i].1 < normalized && yys[i].1 < normalized {
+                xxs[i].0.enforce_equal(&yys[i].0)?;
+                continue;
+            }
+            carrying = true;
             let max_ubound = max(xxs[i].1.clone(), yys[i].1.clone());
             acc += &max_ubound;
             let carry_width =
@@ -295,7 +728,7 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
             };
             let (q, r) = acc.div_rem(&(BigUint::one() << step));
             if i == n - 1 {
-                (&c - &rest).enforce_equal(&FpVar::constant(F::from(q.clone())))?;
+                c.enforce_equal(&FpVar::constant(F::from(q.clone())))?;
             } else {
                 Self::to_bit_array(&c, carry_width)?;
             }
@@ -330,7 +763,7 @@ impl<F: PrimeField, const W: usize> CondSelectGadget<F> for BigUintVar<F, W> {
         for i in 0..true_value.0.len() {
             v.push(cond.select(&true_value.0[i], &false_value.0[i])?);
         }
-        Ok(Self(v))
+        Ok(Self(v.into()))
     }
 }
 
@@ -339,6 +772,76 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
         Self::new_constant(ConstraintSystemRef::None, (v, w))
     }
 
+    pub fn dump(&self, label: &str) {
+        #[cfg(feature = "debug-print")]
+        ark_std::println!("{}: {:?}", label, self);
+        #[cfg(not(feature = "debug-print"))]
+        let _ = label;
+    }
+
+    // With the `debug-check` feature, panics if the value of `actual` differs from the native
+    // result of `op` on the operands' values. Does nothing if some value is unavailable, e.g.,
+    // during setup, or if the feature is off.
+    fn debug_check(
+        op: &str,
+        operands: &[&Self],
+        expected: impl FnOnce(&[BigUint]) -> BigUint,
+        actual: &Self,
+    ) {
+        #[cfg(feature = "debug-check")]
+        {
+            let values = operands.iter().map(|x| x.value()).collect::<Result<Vec<_>, _>>();
+            if let (Ok(values), Ok(actual)) = (values, actual.value()) {
+                let expected = expected(&values);
+                assert!(
+                    expected == actual,
+                    "debug-check: {} diverged on operands of {:?} bits: expected {:#x}, got {:#x}",
+                    op,
+                    values.iter().map(BigUint::bits).collect::<Vec<_>>(),
+                    expected,
+                    actual
+                );
+            }
+        }
+        #[cfg(not(feature = "debug-check"))]
+        let _ = (op, operands, expected, actual);
+    }
+
+    fn bits_value(bits: &[Boolean<F>]) -> BigUint {
+        bits.iter().rev().fold(BigUint::zero(), |acc, b| {
+            (acc << 1) + BigUint::from(b.value().unwrap_or_default() as u8)
+        })
+    }
+
+    pub fn from_limbs(limbs: Vec<FpVar<F>>, limb_bits: usize) -> Result<Self, SynthesisError> {
+        for limb in &limbs {
+            Self::to_bit_array(limb, limb_bits)?;
+        }
+        Ok(Self::from_limbs_unchecked(limbs, limb_bits))
+    }
+
+    pub fn from_limbs_unchecked(limbs: Vec<FpVar<F>>, limb_bits: usize) -> Self {
+        assert!(limb_bits < F::MODULUS_BIT_SIZE as usize);
+        let ubound = (BigUint::one() << limb_bits) - BigUint::one();
+        Self(limbs.into_iter().map(|limb| BitsVar(limb, ubound.clone())).collect())
+    }
+
+    pub fn num_limbs(&self) -> usize {
+        self.0.len()
+    }
+
+    // The assigned limbs, lowest first, e.g., to find the one that fails a range check. Unlike
+    // `value`, this keeps the limbs as they are, which may exceed W bits before `align`.
+    pub fn limb_values(&self) -> Result<Vec<F>, SynthesisError> {
+        self.0.iter().map(|limb| limb.0.value()).collect()
+    }
+
+    // The width l for a freshly allocated value, which then has ceil(l / W) limbs. After
+    // arithmetic, this is the bit length of the upper bound, and the limbs may not be full.
+    pub fn declared_bits(&self) -> usize {
+        self.ubound().bits() as usize
+    }
+
     pub fn ubound(&self) -> BigUint {
         let mut r = BigUint::zero();
 
@@ -366,18 +869,36 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
     }
 
     pub fn align(&self) -> Result<Self, SynthesisError> {
+        Ok(self.align_with_bits()?.0)
+    }
+
+    // Same as `align`, but also returns the little-endian bits of the normalized value, which
+    // are a by-product of the carrying.
+    fn align_with_bits(&self) -> Result<(Self, Vec<Boolean<F>>), SynthesisError> {
+        let _ns = ns!(self.cs(), "align");
         let mut z = vec![];
+        let mut bits = vec![];
+        let mut carry_bits = vec![];
         let mut c = BitsVar::zero();
-        for i in &self.0 {
+        for i in self.0.iter() {
             let v = i.add(&c).unwrap();
             let v_bits = v.to_bit_array(None)?;
             let p = min(W, v_bits.len());
             let (l, r) = v_bits.split_at(p);
             c = BitsVar(Boolean::le_bits_to_fp_var(r)?, v.1 >> p);
             z.push(BitsVar::from(l));
+            bits.extend_from_slice(l);
+            bits.resize(bits.len() + W - p, Boolean::FALSE);
+            carry_bits = r.to_vec();
         }
         z.push(c);
-        Ok(Self(z))
+        bits.extend(carry_bits);
+        while matches!(bits.last(), Some(Boolean::Constant(false))) {
+            bits.pop();
+        }
+        let z = Self(z.into());
+        Self::debug_check("align", &[self], |v| v[0].clone(), &z);
+        Ok((z, bits))
     }
 
     pub fn add_no_carry(&self, other: &Self) -> Self {
@@ -388,7 +909,77 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
         for i in 0..other.0.len() {
             z[i] = z[i].add(&other.0[i]).unwrap();
         }
-        Self(z)
+        Self(z.into())
+    }
+
+    // The sum together with a flag set iff it reached `limit`, e.g., n when adding up
+    // plaintexts, so that circuits can detect an overflow instead of wrapping around.
+    pub fn add_checked(
+        &self,
+        other: &Self,
+        limit: &Self,
+    ) -> Result<(Self, Boolean<F>), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()).or(limit.cs()), "add_checked");
+        let sum = self.add_no_carry(other);
+        let overflow = sum.is_lt(limit)?.not();
+        Ok((sum, overflow))
+    }
+
+    pub fn double(&self) -> Result<Self, SynthesisError> {
+        self.add_no_carry(self).align()
+    }
+
+    pub fn halve(&self) -> Result<Self, SynthesisError> {
+        let cs = self.cs();
+        let _ns = ns!(cs, "halve");
+        let h = self.value().unwrap_or_default() >> 1;
+        let h_bits = (self.ubound().bits() as usize).saturating_sub(1);
+        let h = if cs.is_none() {
+            Self::constant(h, h_bits)?
+        } else {
+            Self::new_witness(cs.clone(), || Ok((h, h_bits)))?
+        };
+        // h + h == self implies that self is even.
+        h.add_no_carry(&h).enforce_equal_unaligned(self)?;
+        Self::debug_check("halve", &[self], |v| &v[0] >> 1, &h);
+        Ok(h)
+    }
+
+    // Witnesses d = self - other with `bits` bits and enforces other + d == self, which implies
+    // self >= other. If self < other, d is set to 0 and the system is left unsatisfied.
+    fn sub_with_bits(&self, other: &Self, bits: usize) -> Result<Self, SynthesisError> {
+        let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "sub");
+        let (x, y) = (self.value().unwrap_or_default(), other.value().unwrap_or_default());
+        let d = if x >= y { x - y } else { BigUint::zero() };
+        let d = if cs.is_none() {
+            Self::constant(d, bits)?
+        } else {
+            Self::new_witness(ns!(cs, "difference_rangecheck"), || Ok((d, bits)))?
+        };
+        other.add_no_carry(&d).enforce_equal_unaligned(self)?;
+        Self::debug_check("sub", &[self, other], |v| &v[0] - &v[1], &d);
+        Ok(d)
+    }
+
+    // Enforces self >= 1 and returns self - 1.
+    pub fn sub_one(&self) -> Result<Self, SynthesisError> {
+        self.sub_with_bits(&Self::constant(BigUint::one(), 1)?, self.ubound().bits() as usize)
+    }
+
+    // Enforces self >= other, where either side may be constant.
+    pub fn enforce_ge(&self, other: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "enforce_ge");
+        self.sub_with_bits(other, self.ubound().bits() as usize)?;
+        Ok(())
+    }
+
+    // Enforces a <= self <= b for arbitrary a and b, constant or not.
+    pub fn enforce_in_interval(&self, a: &Self, b: &Self) -> Result<(), SynthesisError> {
+        let bits = max(self.ubound().bits(), b.ubound().bits()) as usize;
+        self.sub_with_bits(a, bits)?;
+        b.sub_with_bits(self, bits)?;
+        Ok(())
     }
 
     pub fn mul_no_carry(&self, other: &Self) -> Result<Self, SynthesisError> {
@@ -400,16 +991,18 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
                     z[i + j] = z[i + j].add(&self.0[i].mul(&other.0[j]).unwrap()).unwrap();
                 }
             }
-            return Ok(Self(z));
+            return Ok(Self(z.into()));
         }
         let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "mul_no_carry");
 
         let z = {
+            let x = self.0.iter().map(|i| i.value().unwrap_or_default()).collect::<Vec<_>>();
+            let y = other.0.iter().map(|j| j.value().unwrap_or_default()).collect::<Vec<_>>();
             let mut z = vec![(F::zero(), BigUint::zero()); len];
             for i in 0..self.0.len() {
                 for j in 0..other.0.len() {
-                    z[i + j].0 += self.0[i].value().unwrap_or_default()
-                        * other.0[j].value().unwrap_or_default();
+                    z[i + j].0 += x[i] * y[j];
                     z[i + j].1 += &self.0[i].1 * &other.0[j].1;
                 }
             }
@@ -444,7 +1037,56 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
             l.mul_equals(&r, &o)?;
         }
 
-        Ok(Self(z))
+        let z = Self(z.into());
+        Self::debug_check("mul_no_carry", &[self, other], |v| &v[0] * &v[1], &z);
+        Ok(z)
+    }
+
+    // Same as `mul_no_carry`, but switches to Karatsuba for operands of at least the threshold
+    // set by `with_karatsuba_threshold`, which is never by default.
+    pub fn mul_dispatch(&self, other: &Self) -> Result<Self, SynthesisError> {
+        let threshold = KARATSUBA_LIMBS.with(|t| t.get());
+        // Karatsuba needs both operands to span more than the lower half.
+        let h = max(self.0.len(), other.0.len()) / 2;
+        if min(self.0.len(), other.0.len()) < max(threshold, 2)
+            || min(self.0.len(), other.0.len()) <= h
+            || self.is_constant()
+            || other.is_constant()
+        {
+            return self.mul_no_carry(other);
+        }
+        let _ns = ns!(self.cs().or(other.cs()), "karatsuba");
+
+        let (x0, x1) = (Self(self.0[..h].into()), Self(self.0[h..].into()));
+        let (y0, y1) = (Self(other.0[..h].into()), Self(other.0[h..].into()));
+        let z0 = x0.mul_dispatch(&y0)?;
+        let z2 = x1.mul_dispatch(&y1)?;
+        let s = x0.add_no_carry(&x1).mul_dispatch(&y0.add_no_carry(&y1))?;
+
+        let mut z = vec![BitsVar::zero(); self.0.len() + other.0.len() - 1];
+        for (i, limb) in z0.0.iter().enumerate() {
+            z[i] = z[i].add(limb).unwrap();
+        }
+        for (i, limb) in z2.0.iter().enumerate() {
+            z[i + h * 2] = z[i + h * 2].add(limb).unwrap();
+        }
+        // z1 = s - z0 - z2 = x0 * y1 + x1 * y0 has nonnegative limbs bounded by those of s.
+        let zero = BitsVar::zero();
+        for (i, limb) in s.0.iter().enumerate() {
+            let z0 = &z0.0.get(i).unwrap_or(&zero).0;
+            let z2 = &z2.0.get(i).unwrap_or(&zero).0;
+            let z1 = BitsVar(&limb.0 - z0 - z2, limb.1.clone());
+            z[i + h] = z[i + h].add(&z1).unwrap();
+        }
+
+        let z = Self(z.into());
+        Self::debug_check("mul_dispatch", &[self, other], |v| &v[0] * &v[1], &z);
+        Ok(z)
+    }
+
+    // The product with a constant is linear in the limbs of self, so it adds no constraints.
+    pub fn mul_by_constant(&self, k: &BigUint) -> Result<Self, SynthesisError> {
+        self.mul_no_carry(&Self::constant(k.clone(), max(k.bits() as usize, 1))?)
     }
 
     pub fn enforce_congruent_const(&self, other: &Self, m: &Self) -> Result<(), SynthesisError> {
@@ -474,11 +1116,18 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
         l.enforce_equal_unaligned(&r)
     }
 
+    // `m_lbound` sets the width of the quotient, so it has to be a public bound that holds
+    // for every admissible m. For a secret m, it must not be derived from the value of m, which
+    // would then shape the circuit, see `enforce_secret_modulus`. If m is below the bound, the
+    // quotient does not fit and the system is unsatisfiable, but never unsound.
     pub fn rem(&self, m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError> {
         let cs = self.cs().or(m.cs());
+        let _ns = ns!(cs, "rem");
         let (q, r) = {
             let (q, r) =
                 self.value().unwrap_or_default().div_rem(&m.value().unwrap_or(BigUint::one()));
+            #[cfg(all(test, feature = "debug-check"))]
+            let q = q + CORRUPT_QUOTIENT.with(|c| c.replace(false)) as u8;
             let q_ubound = self.ubound().div_ceil(m_lbound);
             let r_ubound = m.ubound();
             if cs.is_none() {
@@ -488,94 +1137,636 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
                 )
             } else {
                 (
-                    Self::new_witness(cs.clone(), || Ok((q, q_ubound.bits() as usize)))?,
-                    Self::new_witness(cs.clone(), || Ok((r, r_ubound.bits() as usize)))?,
+                    Self::new_witness(ns!(cs, "quotient_rangecheck"), || {
+                        Ok((q, q_ubound.bits() as usize))
+                    })?,
+                    Self::new_witness(ns!(cs, "remainder_rangecheck"), || {
+                        Ok((r, r_ubound.bits() as usize))
+                    })?,
                 )
             }
         };
 
         q.mul_no_carry(&m)?.add_no_carry(&r).enforce_equal_unaligned(&self)?;
+        Self::debug_check("rem (quotient)", &[self, m], |v| &v[0] / &v[1], &q);
+        Self::debug_check("rem (remainder)", &[self, m], |v| &v[0] % &v[1], &r);
 
         Ok(r)
     }
 
-    pub fn powm(
-        self,
-        e: &[Boolean<F>],
-        m: &Self,
-        m_lbound: &BigUint,
-    ) -> Result<Self, SynthesisError> {
-        let mut k = 1;
-        while (e.len() - 1) * ((1 << (k + 1)) - k - 2) >= (k * (k + 1) << (2 * k)) {
-            k += 1;
+    // Same as `rem` followed by `r.enforce_lt(m)`, but the returned limbs carry the bounds implied
+    // by r < m, which can be tighter than their widths when m is constant or its top limb is
+    // not full.
+    pub fn rem_reduced(&self, m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError> {
+        let r = self.rem(m, m_lbound)?;
+        r.enforce_lt(m)?;
+        let r_max = if m.is_constant() { m.value()? } else { m.ubound() } - BigUint::one();
+        Ok(Self(
+            r.0.iter()
+                .enumerate()
+                .map(|(i, limb)| BitsVar(limb.0.clone(), min(limb.1.clone(), &r_max >> (W * i))))
+                .collect(),
+        ))
+    }
+
+    // self mod m for self < 2m, e.g., the output of `mont_mul` or the sum of two reduced
+    // values, by subtracting m iff self >= m. Unlike `rem`, there is no quotient to multiply
+    // by m. The result is only below m if the precondition holds.
+    pub fn reduce_once(&self, m: &Self) -> Result<Self, SynthesisError> {
+        let cs = self.cs().or(m.cs());
+        let _ns = ns!(cs, "reduce_once");
+        let (x, y) = (self.value().unwrap_or_default(), m.value().unwrap_or(BigUint::one()));
+        debug_assert!(
+            self.value().is_err() || m.value().is_err() || x < &y * 2u32,
+            "reduce_once: {:#x} is not below twice the modulus {:#x}",
+            x,
+            y
+        );
+        let lt = self.is_lt(m)?;
+        let r = if x < y { x } else { x - y };
+        let r_bits = m.ubound().bits() as usize;
+        let r = if cs.is_none() {
+            Self::constant(r, r_bits)?
+        } else {
+            Self::new_witness(ns!(cs, "remainder_rangecheck"), || Ok((r, r_bits)))?
+        };
+        // `select` wants the same bounds on both sides.
+        let zero = Self(m.0.iter().map(|limb| BitsVar(FpVar::zero(), limb.1.clone())).collect());
+        r.add_no_carry(&lt.select(&zero, m)?).enforce_equal_unaligned(self)?;
+        Self::debug_check("reduce_once", &[self, m], |v| &v[0] % &v[1], &r);
+        Ok(r)
+    }
+
+    // self mod k for a constant k > 0, fully reduced.
+    pub fn mod_constant(&self, k: &BigUint) -> Result<Self, SynthesisError> {
+        let _ns = ns!(self.cs(), "mod_constant");
+        self.rem_reduced(&Self::constant(k.clone(), k.bits() as usize)?, k)
+    }
+
+    // The parity of the lowest limb is the parity of the integer, even for unnormalized limbs.
+    pub fn is_odd(&self) -> Result<Boolean<F>, SynthesisError> {
+        match self.0.first() {
+            Some(limb) => Ok(limb.to_bit_array(None)?.first().cloned().unwrap_or(Boolean::FALSE)),
+            None => Ok(Boolean::FALSE),
         }
-        let mut base_powers =
-            vec![Self::constant(BigUint::one(), m.ubound().bits() as usize)?, self.clone()];
-        for _ in 2..(1 << k) {
-            base_powers.push(base_powers.last().unwrap().mul_no_carry(&self)?.rem(m, m_lbound)?);
+    }
+
+    // A cheap filter against malformed moduli, not a proof that n is a product of two primes:
+    // enforces that n is odd, has exactly the bits it is allocated with, and has none of
+    // `SMALL_PRIMES` as a factor.
+    pub fn enforce_modulus_sane(n: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(n.cs(), "enforce_modulus_sane");
+        n.is_odd()?.enforce_equal(&Boolean::TRUE)?;
+        n.enforce_secret_modulus(n.declared_bits())?;
+        for p in SMALL_PRIMES {
+            n.mod_constant(&BigUint::from(p))?
+                .is_eq_constant(&BigUint::zero())?
+                .enforce_equal(&Boolean::FALSE)?;
         }
-        let mut r = Self::constant(BigUint::one(), m.ubound().bits() as usize)?;
+        Ok(())
+    }
 
-        for (i, chunk) in e.rchunks(k).enumerate() {
-            let k = chunk.len();
-            if i != 0 {
-                for _ in 0..k {
-                    r = r.mul_no_carry(&r)?.rem(m, m_lbound)?;
-                }
-            }
-            let base_power = {
-                let mut inputs = base_powers[..(1 << k)].to_vec();
-                for b in chunk {
-                    inputs = inputs
-                        .chunks(2)
-                        .map(|v| b.select(&v[1], &v[0]))
-                        .collect::<Result<_, _>>()?;
-                }
-                inputs.pop().unwrap()
-            };
-            if i != 0 {
-                r = r.mul_no_carry(&base_power)?.rem(m, m_lbound)?;
-            } else {
-                r = base_power;
-            }
+    // For a modulus only known to the prover, e.g., n in a decryption with only n^2 public:
+    // enforces that self has exactly `bits` bits, and returns the lower bound 2^(bits - 1) to
+    // pass to `rem` and `powm`. It only depends on the public `bits`, so the layout reveals
+    // nothing about self beyond its size.
+    pub fn enforce_secret_modulus(&self, bits: usize) -> Result<BigUint, SynthesisError> {
+        let _ns = ns!(self.cs(), "enforce_secret_modulus");
+        assert!(bits >= 1);
+        self.to_bits_le_truncated(bits)?[bits - 1].enforce_equal(&Boolean::TRUE)?;
+        Ok(pow2_bound(bits - 1))
+    }
+
+    // The best lower bound implied by the metadata alone: a constant is its own bound, while
+    // a variable is only known to be at least 1 (when used as a modulus).
+    pub fn lbound(&self) -> Result<BigUint, SynthesisError> {
+        if self.is_constant() {
+            self.value()
+        } else {
+            Ok(BigUint::one())
         }
+    }
 
-        r.enforce_lt(&m)?;
-        Ok(r)
+    pub fn rem_auto(&self, m: &Self) -> Result<Self, SynthesisError> {
+        // A non-constant modulus makes the quotient as wide as `self`. Use `rem` with a
+        // tighter lower bound when one is known to hold.
+        self.rem(m, &m.lbound()?)
     }
 
-    pub fn powm_const(self, e: &[Boolean<F>], m: &Self) -> Result<Self, SynthesisError> {
-        assert!(m.is_constant());
-        self.powm(e, m, &m.value()?)
+    pub fn rem_by_width(&self, m: &Self) -> Result<Self, SynthesisError> {
+        self.rem(m, &m.width_lbound()?)
     }
 
-    pub fn sub_one_enforce_coprime(&self, other: &Self) -> Result<(), SynthesisError> {
-        let cs = self.cs().or(other.cs());
-        let (x, y) = {
-            let x_bound = other.ubound();
-            let y_bound = self.ubound();
-            let a: BigInt = self.value().unwrap_or_default().into();
-            let a = a - BigInt::one();
-            let b: BigInt = other.value().unwrap_or(BigUint::one()).into();
-            let ExtendedGcd { mut x, mut y, .. } = a.extended_gcd(&b);
-            if x.is_negative() {
-                let k = x.abs().div_ceil(&b);
-                (x, y) = (&x + &b * &k, &y - &a * &k);
-            }
-            let x = x.to_biguint().unwrap();
-            let y = y.abs().to_biguint().unwrap();
-            if cs.is_none() {
-                (
-                    Self::constant(x, x_bound.bits() as usize)?,
-                    Self::constant(y, y_bound.bits() as usize)?,
-                )
+    // A non-constant modulus is taken to be normalized by its public width: a k-bit m is
+    // assumed to be at least 2^(k - 2), as n^2 is for an n with the top bit set. This bounds the quotient by `self / 2^(k - 2)` instead of by `self` as
+    // in `rem_auto`. Nothing is enforced, as a shorter m only makes the quotient overflow its
+    // range check, which leaves `rem` unsatisfiable rather than unsound.
+    fn width_lbound(&self) -> Result<BigUint, SynthesisError> {
+        if self.is_constant() {
+            return self.lbound();
+        }
+        Ok(pow2_bound(self.declared_bits().saturating_sub(2)))
+    }
+
+    // Returns u with u = self * other * R^-1 (mod m) for R = 2^(W * m.0.len()), but u is only
+    // bounded by `self * other / R + m`, and is not reduced below m.
+    pub fn mont_mul(&self, other: &Self, m: &Self) -> Result<Self, SynthesisError> {
+        let cs = self.cs().or(other.cs()).or(m.cs());
+        let _ns = ns!(cs, "mont_mul");
+        let len = m.0.len();
+        let rr = BigUint::one() << (W * len);
+        let xy = self.mul_no_carry(other)?;
+        let (t, u) = {
+            let xy = xy.value().unwrap_or_default();
+            let m = m.value().unwrap_or(BigUint::one());
+            // t = -xy * m^-1 mod R, so that xy + t * m is divisible by R
+            let ExtendedGcd { gcd, x, .. } =
+                BigInt::from(m.clone()).extended_gcd(&rr.clone().into());
+            let t = if gcd.is_one() {
+                let m_inv = x.mod_floor(&rr.clone().into()).to_biguint().unwrap();
+                (&rr - &xy * m_inv % &rr) % &rr
             } else {
-                (
-                    Self::new_witness(cs.clone(), || Ok((x, x_bound.bits() as usize)))?,
-                    Self::new_witness(cs.clone(), || Ok((y, y_bound.bits() as usize)))?,
-                )
-            }
+                BigUint::zero()
+            };
+            let u = (xy + &t * &m) >> (W * len);
+            (t, u)
+        };
+        let u_bits =
+            ((xy.ubound() + (&rr - BigUint::one()) * m.ubound()) >> (W * len)).bits() as usize;
+        let (t, u) = if cs.is_none() {
+            (Self::constant(t, W * len)?, Self::constant(u, u_bits)?)
+        } else {
+            (
+                Self::new_witness(cs.clone(), || Ok((t, W * len)))?,
+                Self::new_witness(cs.clone(), || Ok((u, u_bits)))?,
+            )
+        };
+
+        let shifted =
+            Self(vec![BitsVar::zero(); len].into_iter().chain(u.0.iter().cloned()).collect());
+        xy.add_no_carry(&t.mul_no_carry(m)?).enforce_equal_unaligned(&shifted)?;
+
+        Ok(u)
+    }
+
+    // self * other mod m, reduced by `reducer` but not necessarily below m. With Montgomery,
+    // self is first brought into Montgomery form, so that a single `mont_mul` cancels the
+    // factor R again.
+    pub fn mul_mod_with(
+        &self,
+        other: &Self,
+        reducer: &Reducer<F, W>,
+    ) -> Result<Self, SynthesisError> {
+        reducer.mul(&reducer.enter(self)?, other)
+    }
+
+    // self mod m for a self that is not a product, which Montgomery has no shortcut for, so
+    // it falls back to `rem`.
+    pub fn rem_with(&self, reducer: &Reducer<F, W>) -> Result<Self, SynthesisError> {
+        self.rem(reducer.m, &reducer.m_lbound)
+    }
+
+    // An empty exponent gets the smallest window, and the power is the constant 1.
+    fn window_size(len: usize) -> usize {
+        let mut k = 1;
+        while len.saturating_sub(1) * ((1 << (k + 1)) - k - 2) >= (k * (k + 1) << (2 * k)) {
+            k += 1;
+        }
+        k
+    }
+
+    fn power_table(&self, k: usize, reducer: &Reducer<F, W>) -> Result<Vec<Self>, SynthesisError> {
+        let _ns = ns!(self.cs(), "table");
+        let mut table = vec![reducer.one()?, self.clone()];
+        for _ in 2..(1 << k) {
+            table.push(reducer.mul(table.last().unwrap(), self)?);
+        }
+        Ok(table)
+    }
+
+    fn select_power(table: &[Self], chunk: &[Boolean<F>]) -> Result<Self, SynthesisError> {
+        let mut inputs = table[..(1 << chunk.len())].to_vec();
+        for b in chunk {
+            inputs = inputs.chunks(2).map(|v| b.select(&v[1], &v[0])).collect::<Result<_, _>>()?;
+        }
+        Ok(inputs.pop().unwrap())
+    }
+
+    // table[index] as sum_i table[i] * eq_i(index), where eq_i is the Lagrange polynomial that
+    // is 1 at i and 0 at the other indices, and index is enforced to be below the table size
+    // t. The powers of index take t constraints, and every product with a non-constant limb
+    // one more. `select_power` instead takes t - 1 selections per limb by the bits of the
+    // index, of which only the first layer is free for constant entries. The linear lookup
+    // thus wins for constant tables, and the tree for tables of variables.
+    pub fn lookup_linear(table: &[Self], index: &FpVar<F>) -> Result<Self, SynthesisError> {
+        let t = table.len();
+        let _ns = ns!(index.cs(), "lookup_linear");
+        // The coefficients of a polynomial, lowest first, times (x - j).
+        let mul_linear = |p: &[F], j: usize| {
+            let mut q = vec![F::zero(); p.len() + 1];
+            for (k, c) in p.iter().enumerate() {
+                q[k + 1] += c;
+                q[k] -= *c * F::from(j as u64);
+            }
+            q
+        };
+        let mut powers = vec![FpVar::one()];
+        for _ in 0..t {
+            powers.push(powers.last().unwrap() * index);
+        }
+        let eval =
+            |p: &[F]| p.iter().zip(&powers).fold(FpVar::<F>::zero(), |acc, (c, x)| acc + x * *c);
+
+        eval(&(0..t).fold(vec![F::one()], |p, j| mul_linear(&p, j)))
+            .enforce_equal(&FpVar::zero())?;
+        let selectors = (0..t)
+            .map(|i| {
+                let (p, d) =
+                    (0..t).filter(|&j| j != i).fold((vec![F::one()], F::one()), |(p, d), j| {
+                        (mul_linear(&p, j), d * (F::from(i as u64) - F::from(j as u64)))
+                    });
+                eval(&p.iter().map(|c| *c * d.inverse().unwrap()).collect::<Vec<_>>())
+            })
+            .collect::<Vec<_>>();
+
+        let len = table.iter().map(|x| x.0.len()).max().unwrap_or_default();
+        let zero = BitsVar::zero();
+        let mut z = vec![];
+        for k in 0..len {
+            let limbs = table.iter().map(|x| x.0.get(k).unwrap_or(&zero)).collect::<Vec<_>>();
+            let ubound = limbs.iter().map(|limb| limb.1.clone()).max().unwrap();
+            let limb = limbs
+                .iter()
+                .zip(&selectors)
+                .fold(FpVar::zero(), |acc, (limb, s)| acc + &limb.0 * s);
+            z.push(BitsVar(limb, ubound));
+        }
+        Ok(Self(z.into()))
+    }
+
+    pub fn powm(
+        self,
+        e: &[Boolean<F>],
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        self.powm_with(e, &Reducer::new(m, m_lbound, ReductionStrategy::default())?)
+    }
+
+    // Same as `powm`, with every product reduced by `reducer`.
+    pub fn powm_with(
+        self,
+        e: &[Boolean<F>],
+        reducer: &Reducer<F, W>,
+    ) -> Result<Self, SynthesisError> {
+        let m = reducer.m;
+        let cs = self.cs().or(e.cs()).or(m.cs());
+        let _ns = ns!(cs, "powm");
+        let k = Self::window_size(e.len());
+        // In Montgomery form, the intermediate results are not the plain powers.
+        #[cfg(debug_assertions)]
+        let trace = POWM_TRACE.with(|t| t.get())
+            && reducer.strategy != ReductionStrategy::Montgomery
+            && cs.is_satisfied().unwrap_or(false);
+        let base_powers = reducer.enter(&self)?.power_table(k, reducer)?;
+        let mut r = reducer.one()?;
+
+        for (i, chunk) in e.rchunks(k).enumerate() {
+            if i != 0 {
+                for _ in 0..chunk.len() {
+                    let _ns = ns!(cs, "square");
+                    r = reducer.mul(&r, &r)?;
+                }
+            }
+            let base_power = Self::select_power(&base_powers, chunk)?;
+            if i != 0 {
+                let _ns = ns!(cs, "multiply");
+                r = reducer.mul(&r, &base_power)?;
+            } else {
+                r = base_power;
+            }
+            #[cfg(debug_assertions)]
+            if trace {
+                Self::trace_step(i, &self, &e[e.len().saturating_sub((i + 1) * k)..], m, &r);
+            }
+        }
+
+        let r = reducer.leave(&r)?;
+        r.enforce_lt(m)?;
+        Self::debug_check("powm", &[&self, m], |v| v[0].modpow(&Self::bits_value(e), &v[1]), &r);
+        Ok(r)
+    }
+
+    #[cfg(debug_assertions)]
+    fn trace_step(step: usize, x: &Self, e: &[Boolean<F>], m: &Self, r: &Self) {
+        if let (Ok(x), Ok(m), Ok(r)) = (x.value(), m.value(), r.value()) {
+            let expected = x.modpow(&Self::bits_value(e), &m);
+            assert!(
+                &r % &m == expected,
+                "powm trace: step {} diverged: expected {:#x}, got {:#x}",
+                step,
+                expected,
+                r
+            );
+        }
+        let cs = r.cs().or(m.cs());
+        assert!(
+            cs.is_satisfied().unwrap_or(true),
+            "powm trace: step {} is unsatisfied at {}",
+            step,
+            cs.which_is_unsatisfied().ok().flatten().unwrap_or_default()
+        );
+    }
+
+    pub fn multi_powm(
+        bases: &[Self],
+        exps: &[Vec<Boolean<F>>],
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        Self::multi_powm_with(
+            bases,
+            exps,
+            &Reducer::new(m, m_lbound, ReductionStrategy::default())?,
+        )
+    }
+
+    // Same as `multi_powm`, with every product reduced by `reducer`.
+    pub fn multi_powm_with(
+        bases: &[Self],
+        exps: &[Vec<Boolean<F>>],
+        reducer: &Reducer<F, W>,
+    ) -> Result<Self, SynthesisError> {
+        assert_eq!(bases.len(), exps.len());
+        let m = reducer.m;
+        let cs = bases.cs().or(m.cs());
+        let _ns = ns!(cs, "multi_powm");
+        let len = exps.iter().map(Vec::len).max().unwrap();
+        let exps = exps
+            .iter()
+            .map(|e| [&e[..], &vec![Boolean::FALSE; len - e.len()]].concat())
+            .collect::<Vec<_>>();
+        let k = Self::window_size(len);
+        let tables = bases
+            .iter()
+            .map(|base| reducer.enter(base)?.power_table(k, reducer))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut chunks = exps.iter().map(|e| e.rchunks(k)).collect::<Vec<_>>();
+        let mut r = None::<Self>;
+        while let Some(window) = chunks.iter_mut().map(Iterator::next).collect::<Option<Vec<_>>>() {
+            if let Some(mut x) = r.take() {
+                for _ in 0..window[0].len() {
+                    let _ns = ns!(cs, "square");
+                    x = reducer.mul(&x, &x)?;
+                }
+                r = Some(x);
+            }
+            for (table, chunk) in tables.iter().zip(window) {
+                if chunk.iter().all(|b| matches!(b, Boolean::Constant(false))) {
+                    continue;
+                }
+                let base_power = Self::select_power(table, chunk)?;
+                let _ns = ns!(cs, "multiply");
+                r = Some(match r {
+                    Some(x) => reducer.mul(&x, &base_power)?,
+                    None => base_power,
+                });
+            }
+        }
+
+        let r = match r {
+            Some(r) => reducer.leave(&r)?,
+            None => Self::constant(BigUint::one(), m.ubound().bits() as usize)?,
+        };
+        r.enforce_lt(m)?;
+        Self::debug_check(
+            "multi_powm",
+            &bases.iter().chain([m]).collect::<Vec<_>>(),
+            |v| {
+                v.iter().zip(&exps).fold(BigUint::one(), |acc, (base, e)| {
+                    acc * base.modpow(&Self::bits_value(e), &v[bases.len()]) % &v[bases.len()]
+                })
+            },
+            &r,
+        );
+        Ok(r)
+    }
+
+    // Computes self^e1 * self^e2 for a random split e = e1 + e2 with 0 <= e2 <= e, so that the
+    // prover's square-and-multiply loop only ever sees the shares. The split is enforced in
+    // the circuit, and the result is the same as that of `powm`.
+    pub fn powm_blinded<R: RngCore + CryptoRng>(
+        &self,
+        e: &[Boolean<F>],
+        m: &Self,
+        m_lbound: &BigUint,
+        rng: &mut R,
+    ) -> Result<Self, SynthesisError> {
+        let cs = e.cs();
+        if cs.is_none() {
+            return self.clone().powm(e, m, m_lbound);
+        }
+        let e_val = Self::bits_value(e);
+        let e2 = rng.gen_biguint_range(&BigUint::zero(), &(&e_val + BigUint::one()));
+        let e1 = &e_val - &e2;
+        let to_bits = |x: &BigUint| (0..e.len()).map(|i| x.bit(i as u64)).collect::<Vec<_>>();
+        let e1 = Vec::<Boolean<F>>::new_witness(cs.clone(), || Ok(to_bits(&e1)))?;
+        let e2 = Vec::<Boolean<F>>::new_witness(cs, || Ok(to_bits(&e2)))?;
+
+        let from_bits = |bits: &[Boolean<F>]| Self(bits.chunks(W).map(BitsVar::from).collect());
+        from_bits(&e1).add_no_carry(&from_bits(&e2)).enforce_equal_unaligned(&from_bits(e))?;
+        Self::multi_powm(&[self.clone(), self.clone()], &[e1, e2], m, m_lbound)
+    }
+
+    // Enforces self < 2^k, e.g., to bound the cost of using self as an exponent. The limbs
+    // entirely above bit k have to be zero and the one containing it is range-checked, while
+    // limbs whose upper bound already fits cost nothing.
+    pub fn enforce_bit_length(&self, k: usize) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs(), "enforce_bit_length");
+        for (i, limb) in self.normalized()?.0.iter().enumerate() {
+            let allowed = min(k.saturating_sub(i * W), W);
+            if limb.1.bits() as usize <= allowed {
+                continue;
+            }
+            if allowed == 0 {
+                limb.0.enforce_equal(&FpVar::zero())?;
+            } else {
+                Self::to_bit_array(&limb.0, allowed)?;
+            }
+        }
+        Ok(())
+    }
+
+    // The lowest `bits` bits of self, after enforcing that the remaining ones are zero.
+    pub fn to_bits_le_truncated(&self, bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let mut e = self.to_bits_le()?;
+        if e.len() > bits {
+            e[bits..]
+                .iter()
+                .fold(FpVar::zero(), |acc, b| acc + FpVar::from(b.clone()))
+                .enforce_equal(&FpVar::zero())?;
+            e.truncate(bits);
+        }
+        Ok(e)
+    }
+
+    // The base-2^window digits of self, lowest first, e.g., for a windowed exponentiation that
+    // selects by whole digits. Each digit packs `window` bits of the decomposition, which
+    // range-checks it to be below 2^window. There are always ceil(bits / window) digits for
+    // the bit length of the upper bound, whatever the value.
+    pub fn to_window_digits(&self, window: usize) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        assert!(window >= 1 && window < F::MODULUS_BIT_SIZE as usize);
+        let _ns = ns!(self.cs(), "to_window_digits");
+        let (_, mut bits) = self.align_with_bits()?;
+        let len = self.declared_bits().div_ceil(window);
+        bits.resize(len * window, Boolean::FALSE);
+        bits.chunks(window).map(Boolean::le_bits_to_fp_var).collect()
+    }
+
+    // Same as `powm` with the bits of `e`, but only loops over the lowest `e_bits` of them, so
+    // the cost does not depend on the width `e` is allocated with.
+    pub fn powm_with_exp_bits(
+        &self,
+        e: &Self,
+        e_bits: usize,
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        self.clone().powm(&e.to_bits_le_truncated(e_bits)?, m, m_lbound)
+    }
+
+    // self^(e^s) mod m without allocating e^s: the exponent is computed as an integer with
+    // `mul_no_carry`, and its bits come out of the single `align` that normalizes it. With
+    // s = 2 and e = n, this is r^(n^2) as in Damgard-Jurik.
+    pub fn powm_pow(
+        &self,
+        e: &Self,
+        s: usize,
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        assert!(s >= 1);
+        let mut e_pow = e.clone();
+        for _ in 1..s {
+            e_pow = e_pow.normalized()?.mul_no_carry(e)?;
+        }
+        let (_, bits) = e_pow.align_with_bits()?;
+        self.clone().powm(&bits, m, m_lbound)
+    }
+
+    pub fn powm_const(self, e: &[Boolean<F>], m: &Self) -> Result<Self, SynthesisError> {
+        assert!(m.is_constant());
+        self.powm(e, m, &m.value()?)
+    }
+
+    // self^e mod m for an exponent known when building the circuit. Square-and-multiply reads
+    // the bits of e directly, so a zero bit costs only the squaring, and every set bit below
+    // the leading one costs one multiplication. Unlike `powm`, there is no table to select
+    // from, which is cheaper when e is sparse.
+    pub fn powm_public_exp(
+        &self,
+        e: &BigUint,
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        let cs = self.cs().or(m.cs());
+        let _ns = ns!(cs, "powm_public_exp");
+        let mut r = if e.is_zero() {
+            Self::constant(BigUint::one(), m.ubound().bits() as usize)?
+        } else {
+            self.clone()
         };
+        for i in (0..e.bits().saturating_sub(1)).rev() {
+            {
+                let _ns = ns!(cs, "square");
+                r = r.mul_no_carry(&r)?.rem(m, m_lbound)?;
+            }
+            if e.bit(i) {
+                let _ns = ns!(cs, "multiply");
+                r = r.mul_no_carry(self)?.rem(m, m_lbound)?;
+            }
+        }
+
+        r.enforce_lt(m)?;
+        Self::debug_check("powm_public_exp", &[self, m], |v| v[0].modpow(e, &v[1]), &r);
+        Ok(r)
+    }
+
+    fn alloc_bezout(
+        cs: ConstraintSystemRef<F>,
+        a: BigInt,
+        b: BigInt,
+        x_bits: usize,
+        y_bits: usize,
+    ) -> Result<(Self, Self), SynthesisError> {
+        let ExtendedGcd { mut x, mut y, .. } = a.extended_gcd(&b);
+        if x.is_negative() {
+            let k = x.abs().div_ceil(&b);
+            (x, y) = (&x + &b * &k, &y - &a * &k);
+        }
+        let x = x.to_biguint().unwrap();
+        let y = y.abs().to_biguint().unwrap();
+        if cs.is_none() {
+            Ok((Self::constant(x, x_bits)?, Self::constant(y, y_bits)?))
+        } else {
+            Ok((
+                Self::new_witness(cs.clone(), || Ok((x, x_bits)))?,
+                Self::new_witness(cs.clone(), || Ok((y, y_bits)))?,
+            ))
+        }
+    }
+
+    pub fn enforce_coprime(&self, other: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "enforce_coprime");
+        let (x, y) = Self::alloc_bezout(
+            self.cs().or(other.cs()),
+            self.value().unwrap_or_default().into(),
+            other.value().unwrap_or(BigUint::one()).into(),
+            other.ubound().bits() as usize,
+            self.ubound().bits() as usize,
+        )?;
+        self.mul_no_carry(&x)?.enforce_equal_unaligned(
+            &other.mul_no_carry(&y)?.add_no_carry(&BigUintVar::constant(BigUint::one(), 1)?),
+        )
+    }
+
+    // lcm(self, other) for nonzero operands: with the witnessed g = gcd(self, other), self = g * u
+    // and other = g * v for coprime u and v, so the result u * other is the lcm.
+    pub fn lcm(&self, other: &Self) -> Result<Self, SynthesisError> {
+        let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "lcm");
+        let (a, b) = (self.value().unwrap_or_default(), other.value().unwrap_or_default());
+        let g = a.gcd(&b).max(BigUint::one());
+        let (a_bits, b_bits) = (self.ubound().bits() as usize, other.ubound().bits() as usize);
+        let values =
+            [(&a / &g, a_bits), (&b / &g, b_bits), (a.lcm(&b), a_bits + b_bits), (g, a_bits)];
+        let [u, v, l, g] = values.map(|(x, bits)| {
+            if cs.is_none() {
+                Self::constant(x, bits)
+            } else {
+                Self::new_witness(cs.clone(), || Ok((x, bits)))
+            }
+        });
+        let (u, v, l, g) = (u?, v?, l?, g?);
+        g.mul_no_carry(&u)?.enforce_equal_unaligned(self)?;
+        g.mul_no_carry(&v)?.enforce_equal_unaligned(other)?;
+        u.enforce_coprime(&v)?;
+        u.mul_no_carry(other)?.enforce_equal_unaligned(&l)?;
+        Self::debug_check("lcm", &[self, other], |x| x[0].lcm(&x[1]), &l);
+        Ok(l)
+    }
+
+    pub fn sub_one_enforce_coprime(&self, other: &Self) -> Result<(), SynthesisError> {
+        let _ns = ns!(self.cs().or(other.cs()), "sub_one_enforce_coprime");
+        let a: BigInt = self.value().unwrap_or_default().into();
+        let (x, y) = Self::alloc_bezout(
+            self.cs().or(other.cs()),
+            a - BigInt::one(),
+            other.value().unwrap_or(BigUint::one()).into(),
+            other.ubound().bits() as usize,
+            self.ubound().bits() as usize,
+        )?;
         self.mul_no_carry(&x)?.enforce_equal_unaligned(
             &other
                 .mul_no_carry(&y)?
@@ -583,20 +1774,99 @@ impl<F: PrimeField, const W: usize> BigUintVar<F, W> {
                 .add_no_carry(&BigUintVar::constant(BigUint::one(), 1)?),
         )
     }
+
+    pub fn paillier_l(x: &Self, n: &Self) -> Result<Self, SynthesisError> {
+        Self::damgard_jurik_l(x, n, n)
+    }
+
+    pub fn damgard_jurik_l(x: &Self, n: &Self, ns: &Self) -> Result<Self, SynthesisError> {
+        let cs = x.cs().or(n.cs()).or(ns.cs());
+        let _ns = ns!(cs, "l");
+        let l = {
+            let x = x.value().unwrap_or_default();
+            let l = if x.is_zero() {
+                BigUint::zero()
+            } else {
+                (x - BigUint::one()) / n.value().unwrap_or(BigUint::one())
+            };
+            let bits = ns.ubound().bits() as usize;
+            if cs.is_none() {
+                Self::constant(l, bits)?
+            } else {
+                Self::new_witness(cs, || Ok((l, bits)))?
+            }
+        };
+        l.mul_no_carry(n)?
+            .add_no_carry(&Self::constant(BigUint::one(), 1)?)
+            .enforce_equal_unaligned(x)?;
+        l.enforce_lt(ns)?;
+        Self::debug_check("l", &[x, n], |v| (&v[0] - BigUint::one()) / &v[1], &l);
+        Ok(l)
+    }
+
+    pub fn crt_combine(
+        xp: &Self,
+        xq: &Self,
+        p: &Self,
+        q: &Self,
+        p_inv_mod_q: &Self,
+    ) -> Result<Self, SynthesisError> {
+        let cs = xp.cs().or(xq.cs()).or(p.cs()).or(q.cs()).or(p_inv_mod_q.cs());
+        let _ns = ns!(cs, "crt_combine");
+        xp.enforce_lt(p)?;
+        xq.enforce_lt(q)?;
+
+        let (h, t, x) = {
+            let xp = xp.value().unwrap_or_default();
+            let xq = xq.value().unwrap_or_default();
+            let p_value = p.value().unwrap_or(BigUint::one());
+            let q_value = q.value().unwrap_or(BigUint::one());
+            let h = (xq + &q_value - &xp % &q_value) * p_inv_mod_q.value().unwrap_or_default()
+                % &q_value;
+            let x = xp + &p_value * &h;
+            let t = &x / &q_value;
+            let h_bits = q.ubound().bits() as usize;
+            let t_bits = p.ubound().bits() as usize;
+            let x_bits = (p.ubound() * q.ubound()).bits() as usize;
+            if cs.is_none() {
+                (Self::constant(h, h_bits)?, Self::constant(t, t_bits)?, Self::constant(x, x_bits)?)
+            } else {
+                (
+                    Self::new_witness(cs.clone(), || Ok((h, h_bits)))?,
+                    Self::new_witness(cs.clone(), || Ok((t, t_bits)))?,
+                    Self::new_witness(cs.clone(), || Ok((x, x_bits)))?,
+                )
+            }
+        };
+        // `x = xp + p * h` with `h < q` pins `x < p * q` and `x = xp (mod p)`,
+        // while `x = xq + q * t` gives `x = xq (mod q)`.
+        h.enforce_lt(q)?;
+        p.mul_no_carry(&h)?.add_no_carry(xp).enforce_equal_unaligned(&x)?;
+        q.mul_no_carry(&t)?.add_no_carry(xq).enforce_equal_unaligned(&x)?;
+        Self::debug_check("crt_combine", &[xp, p, &h], |v| &v[0] + &v[1] * &v[2], &x);
+
+        Ok(x)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::error::Error;
+    use std::{error::Error, time::Instant};
 
     use ark_bn254::Fr;
-    use ark_relations::r1cs::ConstraintSystem;
-    use num::bigint::RandBigInt;
-    use rand::thread_rng;
+    use ark_crypto_primitives::sponge::{
+        constraints::CryptographicSpongeVar,
+        poseidon::{constraints::PoseidonSpongeVar, find_poseidon_ark_and_mds, PoseidonConfig},
+    };
+    use ark_ff::UniformRand;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisMode};
+    use num_prime::RandPrime;
+    use rand::{thread_rng, Rng};
 
     use super::*;
 
     const W: usize = 32;
+    const N: usize = crate::TEST_BITS;
 
     #[test]
     fn test() -> Result<(), Box<dyn Error>> {
@@ -652,6 +1922,1181 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_pow2_bound() -> Result<(), Box<dyn Error>> {
+        for bits in [0, 1, 31, 32, 33, 2048] {
+            assert_eq!(pow2_bound(bits), BigUint::one() << bits);
+            assert_eq!(pow2_bound(bits).bits() as usize, bits + 1);
+        }
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m = BigUintVar::<Fr, W>::constant(BigUint::from(12345u32), 64)?;
+        let x = BigUintVar::<Fr, W>::new_witness(cs, || Ok((BigUint::from(12345u32), 64)))?;
+        assert_eq!(m.lbound()?, BigUint::from(12345u32));
+        assert_eq!(x.lbound()?, BigUint::one());
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_in_interval() -> Result<(), Box<dyn Error>> {
+        let a = BigUint::from(1000u32);
+        let b = BigUint::from(5000u32);
+        for (x, satisfied) in
+            [(999u32, false), (1000, true), (3000, true), (5000, true), (5001, false)]
+        {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::from(x), 64)))?;
+            let a = BigUintVar::<Fr, W>::constant(a.clone(), 64)?;
+            let b = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((b.clone(), 64)))?;
+            x.enforce_in_interval(&a, &b)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_neq() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(100);
+        for (y, satisfied) in
+            [(x.clone(), false), (&x + BigUint::one(), true), (BigUint::zero(), true)]
+        {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), 200)))?;
+            x_var.enforce_neq(&y_var)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+
+            // Unnormalized limbs representing the same integers.
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), 200)))?;
+            x_var.double()?.enforce_neq(&y_var.add_no_carry(&y_var))?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_lt() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(100) | (BigUint::one() << 99);
+        for (y, expected) in [
+            (&x + BigUint::one(), true),
+            (&x << 50, true),
+            (x.clone(), false),
+            (&x - BigUint::one(), false),
+            (BigUint::zero(), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), 200)))?;
+            assert_eq!(x_var.is_lt(&y_var)?.value()?, expected);
+            assert_eq!(y_var.is_lt(&x_var)?.value()?, y > x);
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_align_with() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(100);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+        let xx_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((&x * &x, 200)))?;
+        let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 300)))?;
+        let one = BigUintVar::<Fr, W>::constant(BigUint::one(), 1)?;
+        for (a, b) in [
+            (x_var.double()?, y_var.add_no_carry(&y_var)),
+            (x_var.mul_no_carry(&x_var)?, xx_var.mul_no_carry(&one)?),
+            (y_var.clone(), x_var.clone()),
+        ] {
+            let (a, b) = a.align_with(&b)?;
+            assert_eq!(a.0.len(), b.0.len());
+            for (a, b) in a.0.iter().zip(b.0.iter()) {
+                assert!(a.1.bits() as usize <= W && b.1.bits() as usize <= W);
+                assert_eq!(a.value()?, b.value()?);
+            }
+        }
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_bit_length() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        for k in [100, 96, 1] {
+            for (x, satisfied) in [
+                (rng.gen_biguint(k as u64) | (BigUint::one() << (k - 1)), true),
+                (BigUint::zero(), true),
+                (BigUint::one() << k, false),
+                (rng.gen_biguint(200) | (BigUint::one() << 199), false),
+            ] {
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 200)))?;
+                x_var.enforce_bit_length(k)?;
+                assert_eq!(cs.is_satisfied()?, satisfied);
+
+                // Unnormalized limbs of 2 * x, which has one more bit.
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 200)))?;
+                x_var.add_no_carry(&x_var).enforce_bit_length(k + 1)?;
+                assert_eq!(cs.is_satisfied()?, satisfied);
+            }
+        }
+
+        // Nothing to check if the upper bound already fits.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::one(), 100)))?;
+        let before = cs.num_constraints();
+        x_var.enforce_bit_length(100)?;
+        assert_eq!(cs.num_constraints(), before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_checked() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let limit = rng.gen_biguint(100) | (BigUint::one() << 99);
+        let x = rng.gen_biguint_below(&limit);
+        for (y, overflow) in [
+            (BigUint::zero(), false),
+            (&limit - &x - BigUint::one(), false),
+            (&limit - &x, true),
+            (&limit - &x + BigUint::one(), true),
+            (limit.clone(), true),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), 100)))?;
+            let limit_var =
+                BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((limit.clone(), 100)))?;
+            let (sum, flag) = x_var.add_checked(&y_var, &limit_var)?;
+            assert_eq!(sum.value()?, &x + &y);
+            assert_eq!(flag.value()?, overflow);
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_num_limbs() -> Result<(), Box<dyn Error>> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        for bits in [N, N * 2, N + 1, 1] {
+            let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::one(), bits)))?;
+            assert_eq!(x.declared_bits(), bits);
+            assert_eq!(x.num_limbs(), bits.div_ceil(W));
+        }
+        let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::one(), N)))?;
+        let xx = x.mul_no_carry(&x)?;
+        assert_eq!(xx.num_limbs(), N / W * 2 - 1);
+        assert_eq!(xx.declared_bits(), xx.ubound().bits() as usize);
+        assert!(xx.declared_bits() <= N * 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_limb_values() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(N as u64), N)))?;
+        // The limbs of x are canonical, while those of x^2 are not.
+        for z in [x.clone(), x.mul_no_carry(&x)?] {
+            let limbs = z.limb_values()?;
+            assert_eq!(limbs.len(), z.num_limbs());
+            let value = limbs
+                .iter()
+                .rev()
+                .fold(BigUint::zero(), |acc, limb| (acc << W) + Into::<BigUint>::into(*limb));
+            assert_eq!(value, z.value()?);
+        }
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::one(), N)))?;
+        assert!(x.limb_values().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_array_alloc() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let xs: [BigUint; 8] = std::array::from_fn(|_| rng.gen_biguint(N as u64));
+        let vars = BigUintVarArray::<Fr, W, 8>::new_witness(cs.clone(), || Ok((xs.clone(), N)))?;
+        assert_eq!(vars.value()?, xs);
+        for (var, x) in vars.0.iter().zip(&xs) {
+            assert_eq!(var.value()?, *x);
+            assert_eq!(var.num_limbs(), N.div_ceil(W));
+        }
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_eq_constant() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(N as u64);
+        for (c, expected) in [
+            (x.clone(), true),
+            (&x + BigUint::one(), false),
+            (&x ^ (BigUint::one() << (N - 1)), false),
+            (&x + (BigUint::one() << N), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N)))?;
+            let before = cs.num_witness_variables();
+            assert_eq!(x_var.is_eq_constant(&c)?.value()?, expected);
+            let constant_cost = cs.num_witness_variables() - before;
+            assert_eq!(x_var.add_no_carry(&x_var).is_eq_constant(&(&c * 2u32))?.value()?, expected);
+            let before = cs.num_witness_variables();
+            if c.bits() as usize <= N {
+                let c_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((c.clone(), N)))?;
+                assert_eq!(x_var.is_eq(&c_var)?.value()?, expected);
+                assert!(constant_cost < cs.num_witness_variables() - before);
+            }
+            assert!(cs.is_satisfied()?);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N)))?;
+            x_var.enforce_eq_constant(&c)?;
+            assert_eq!(cs.is_satisfied()?, expected);
+
+            // Setup goes through even if c is out of reach.
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            cs.set_mode(SynthesisMode::Setup);
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N)))?;
+            x_var.enforce_eq_constant(&c)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_blinded() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(256) | BigUint::one();
+        let x = rng.gen_biguint_below(&m);
+        let e = rng.gen_biguint(128);
+
+        let mut results = vec![];
+        for _ in 0..2 {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 256)))?;
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 256)))?;
+            let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((e.clone(), 128)))?;
+            let e_bits = e_var.to_bits_le()?;
+            let y = x_var.powm_blinded(&e_bits, &m_var, &BigUint::one(), rng)?;
+            assert_eq!(x_var.powm(&e_bits, &m_var, &BigUint::one())?.value()?, y.value()?);
+            assert!(cs.is_satisfied()?);
+            results.push(y.value()?);
+        }
+        assert_eq!(results[0], x.modpow(&e, &m));
+        assert_eq!(results[0], results[1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_with_exp_bits() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(256) | BigUint::one();
+        let x = rng.gen_biguint_below(&m);
+
+        let mut counts = vec![];
+        for e_bits in [256, 2048] {
+            let e = pow2_bound(e_bits) - BigUint::one();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 256)))?;
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 256)))?;
+            let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((e.clone(), 2048)))?;
+            let before = cs.num_constraints();
+            let y = x_var.powm_with_exp_bits(&e_var, e_bits, &m_var, &BigUint::one())?;
+            counts.push(cs.num_constraints() - before);
+            assert_eq!(y.value()?, x.modpow(&e, &m));
+            assert!(cs.is_satisfied()?);
+        }
+        assert!(counts[0] * 6 < counts[1]);
+
+        // A set bit above `e_bits` is rejected.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 256)))?;
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 256)))?;
+        let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((pow2_bound(256), 2048)))?;
+        x_var.powm_with_exp_bits(&e_var, 256, &m_var, &BigUint::one())?;
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_public_exp() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(256) | BigUint::one();
+        let x = rng.gen_biguint_below(&m);
+        let k = 64;
+
+        let run = |e: &BigUint| -> Result<usize, Box<dyn Error>> {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 256)))?;
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 256)))?;
+            let before = cs.num_constraints();
+            let y = x_var.powm_public_exp(e, &m_var, &BigUint::one())?;
+            let count = cs.num_constraints() - before;
+            assert_eq!(y.value()?, x.modpow(e, &m));
+            let e_var = BigUintVar::<Fr, W>::constant(e.clone(), k + 1)?;
+            assert_eq!(
+                x_var.powm(&e_var.to_bits_le()?, &m_var, &BigUint::one())?.value()?,
+                y.value()?
+            );
+            assert!(cs.is_satisfied()?);
+            Ok(count)
+        };
+
+        for e in [0u32, 1, 2, 3] {
+            run(&BigUint::from(e))?;
+        }
+        // All exponents have k + 1 bits and thus k squarings, so the costs only differ in the
+        // multiplications.
+        let base = run(&pow2_bound(k))?;
+        let multiply = run(&(pow2_bound(k) + BigUint::one()))? - base;
+        assert!(multiply > 0);
+        for _ in 0..4 {
+            let e = rng.gen_biguint(k as u64) | pow2_bound(k);
+            assert_eq!(run(&e)?, base + (0..k as u64).filter(|&i| e.bit(i)).count() * multiply);
+        }
+        assert_eq!(run(&(pow2_bound(k + 1) - BigUint::one()))?, base + k * multiply);
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_window_digits() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(N as u64), N)))?;
+        let xx = x.mul_no_carry(&x)?;
+        for z in [x, xx, BigUintVar::constant(BigUint::zero(), N)?] {
+            for window in [1, 3, 4, 7, W, W + 8] {
+                let digits = z.to_window_digits(window)?;
+                assert_eq!(digits.len(), z.declared_bits().div_ceil(window));
+                let value = digits.iter().rev().fold(BigUint::zero(), |acc, d| {
+                    (acc << window) + Into::<BigUint>::into(d.value().unwrap())
+                });
+                assert_eq!(value, z.value()?);
+                assert!(digits
+                    .iter()
+                    .all(|d| Into::<BigUint>::into(d.value().unwrap()).bits() as usize <= window));
+            }
+        }
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_input_trimmed() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        for (bits, len) in [(200, 7), (224, 7), (225, 8), (256, 8)] {
+            let x = rng.gen_biguint(bits as u64) | (BigUint::one() << (bits - 1));
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var =
+                BigUintVar::<Fr, W>::new_input_trimmed(cs.clone(), || Ok((x.clone(), 256)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((&x + 1u32, 256)))?;
+            x_var.enforce_lt(&y_var)?;
+            assert_eq!(x_var.0.len(), len);
+            assert_eq!(x_var.value()?, x);
+            assert_eq!(
+                BigUintVar::<Fr, W>::inputize_trimmed(&x, 256),
+                cs.borrow().unwrap().instance_assignment[1..]
+            );
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    fn test_powm_trace() -> Result<(), Box<dyn Error>> {
+        // With a lower bound far above m, every quotient has a single bit. For x = 2 and
+        // e = 2^70, the intermediate values are 2^(2^j) after j + 1 exponent bits, which stay
+        // below m until 2^128, so the first window that squares past 2^64 is unsatisfied.
+        let m = pow2_bound(64) + BigUint::from(13u32);
+        let e = pow2_bound(70);
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 65)))?;
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::from(2u32), 65)))?;
+        let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((e, 71)))?;
+        let e_bits = e_var.to_bits_le()?;
+
+        let panic = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            with_powm_trace(|| x_var.clone().powm(&e_bits, &m_var, &pow2_bound(200)))
+        }))
+        .unwrap_err();
+        let message = panic.downcast_ref::<String>().unwrap();
+        let k = BigUintVar::<Fr, W>::window_size(71);
+        assert!(message.contains(&format!("step {} is unsatisfied", 7 / k)), "{}", message);
+
+        // Without the trace, the same gadget only leaves the system unsatisfied.
+        x_var.powm(&e_bits, &m_var, &pow2_bound(200))?;
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mul_no_carry_cost() -> Result<(), Box<dyn Error>> {
+        // The partial products x_i * y_j are never materialized: only the 2 * len - 1 output
+        // limbs are witnessed, and they are checked by as many evaluations of the product.
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(N as u64), N)))?;
+        let y =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(N as u64), N)))?;
+        let (constraints, witnesses) = (cs.num_constraints(), cs.num_witness_variables());
+        let z = x.mul_no_carry(&y)?;
+        assert_eq!(z.value()?, x.value()? * y.value()?);
+        assert_eq!(cs.num_witness_variables() - witnesses, N / W * 2 - 1);
+        assert_eq!(cs.num_constraints() - constraints, N / W * 2 - 1);
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mul_dispatch() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        for bits in [1024, 4096, 1000] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || {
+                Ok((rng.gen_biguint(bits as u64), bits))
+            })?;
+            let y = BigUintVar::<Fr, W>::new_witness(cs.clone(), || {
+                Ok((rng.gen_biguint(bits as u64 / 2), bits / 2 + W * 3))
+            })?;
+            let expected = x.value()? * y.value()?;
+            let mut costs = vec![];
+            for threshold in [KARATSUBA_THRESHOLD, 2, 8] {
+                for (a, b) in [(&x, &x), (&x, &y)] {
+                    let before = cs.num_constraints();
+                    let z = with_karatsuba_threshold(threshold, || a.mul_dispatch(b))?;
+                    costs.push(cs.num_constraints() - before);
+                    assert_eq!(z.value()?, a.value()? * b.value()?);
+                    assert_eq!(z.0.len(), a.0.len() + b.0.len() - 1);
+                    // The result can be reduced like that of `mul_no_carry`.
+                    z.enforce_equal_unaligned(&a.mul_no_carry(b)?)?;
+                }
+            }
+            assert_eq!(with_karatsuba_threshold(2, || x.mul_dispatch(&y))?.value()?, expected);
+            // The default picks the cheaper schoolbook path, even at 4096 bits.
+            for (i, cost) in costs.iter().enumerate().skip(2) {
+                assert!(*cost > costs[i % 2]);
+            }
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_pow() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let n = rng.gen_biguint(128) | BigUint::one();
+        let nn = &n * &n;
+        let nnn = &nn * &n;
+        let r = rng.gen_biguint_below(&n);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), 128)))?;
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), 256)))?;
+        let nnn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nnn.clone(), 384)))?;
+        let r_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((r.clone(), 128)))?;
+
+        let nn_bits =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((nn.clone(), 256)))?.to_bits_le()?;
+        let expected = r_var.clone().powm(&nn_bits, &nnn_var, &BigUint::one())?;
+        let y = r_var.powm_pow(&n_var, 2, &nnn_var, &BigUint::one())?;
+        assert_eq!(y.value()?, expected.value()?);
+        assert_eq!(y.value()?, r.modpow(&nn, &nnn));
+
+        let y = r_var.powm_pow(&n_var, 3, &nnn_var, &BigUint::one())?;
+        assert_eq!(y.value()?, r.modpow(&nnn, &nnn));
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_input_canonical() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(100) | (BigUint::one() << W);
+        let canonical = BigUintVar::<Fr, W>::inputize(&x, 100);
+        assert_eq!(canonical.len(), 4);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((x.clone(), 100)))?;
+        assert_eq!(cs.borrow().unwrap().instance_assignment[1..], canonical[..]);
+        assert_eq!(x_var.value()?, x);
+        assert!(cs.is_satisfied()?);
+
+        // The same integer with 2^W moved from the second limb into the first one.
+        {
+            let mut cs = cs.borrow_mut().unwrap();
+            cs.instance_assignment[1] += Fr::from(BigUint::one() << W);
+            cs.instance_assignment[2] -= Fr::one();
+        }
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup_linear() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let values = (0..16).map(|_| rng.gen_biguint(256)).collect::<Vec<_>>();
+        let index = 11;
+
+        for constant in [true, false] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let table = values
+                .iter()
+                .map(|x| {
+                    if constant {
+                        BigUintVar::<Fr, W>::constant(x.clone(), 256)
+                    } else {
+                        BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 256)))
+                    }
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let bits = Vec::<Boolean<Fr>>::new_witness(cs.clone(), || {
+                Ok((0..4).map(|i| index >> i & 1 == 1).collect::<Vec<_>>())
+            })?;
+            let index_var = Boolean::le_bits_to_fp_var(&bits)?;
+
+            let before = cs.num_constraints();
+            let linear = BigUintVar::lookup_linear(&table, &index_var)?;
+            let linear_cost = cs.num_constraints() - before;
+            let before = cs.num_constraints();
+            let tree = BigUintVar::select_power(&table, &bits)?;
+            let tree_cost = cs.num_constraints() - before;
+
+            assert_eq!(linear.value()?, values[index]);
+            assert_eq!(tree.value()?, values[index]);
+            assert!(cs.is_satisfied()?);
+            assert_eq!(linear_cost < tree_cost, constant);
+        }
+
+        // An index outside the table is rejected.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let table = values
+            .iter()
+            .map(|x| BigUintVar::<Fr, W>::constant(x.clone(), 256))
+            .collect::<Result<Vec<_>, _>>()?;
+        let index_var = FpVar::new_witness(cs.clone(), || Ok(Fr::from(16u64)))?;
+        BigUintVar::lookup_linear(&table, &index_var)?;
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sub_one() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        for x in [BigUint::one(), rng.gen_biguint(100), BigUint::one() << 99] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 100)))?;
+            assert_eq!(x_var.sub_one()?.value()?, &x - BigUint::one());
+            assert!(cs.is_satisfied()?);
+        }
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::zero(), 100)))?;
+        x_var.sub_one()?;
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_new_witness_packed() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(2048);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let unpacked = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 2048)))?;
+        let unpacked_count = cs.num_constraints();
+        let packed_index = cs.num_witness_variables();
+        let packed = BigUintVar::<Fr, W>::new_witness_packed(cs.clone(), || Ok((x.clone(), 2048)))?;
+        let packed_count = cs.num_constraints() - unpacked_count;
+        assert!(cs.is_satisfied()?);
+        assert_eq!(packed.value()?, x);
+        assert_eq!(packed.ubound(), unpacked.ubound());
+        assert!(packed_count < unpacked_count);
+        unpacked.enforce_equal_unaligned(&packed)?;
+        assert!(cs.is_satisfied()?);
+
+        // Pushing the packed element out of range breaks its decomposition.
+        cs.borrow_mut().unwrap().witness_assignment[packed_index] +=
+            Fr::from(BigUint::one() << (W * 7));
+        assert!(!cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "debug-check")]
+    #[should_panic(expected = "debug-check: rem (quotient) diverged")]
+    fn test_debug_check() {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(512), 512)))
+            .unwrap();
+        let m =
+            BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((rng.gen_biguint(256), 256))).unwrap();
+        x.rem(&m, &BigUint::one()).unwrap();
+        CORRUPT_QUOTIENT.with(|c| c.set(true));
+        x.rem(&m, &BigUint::one()).unwrap();
+    }
+
+    #[test]
+    fn test_rem_auto() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let a = rng.gen_biguint_below(&nn);
+        let b = rng.gen_biguint_below(&nn);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+        let a_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((a.clone(), N * 2)))?;
+        let b_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((b.clone(), N * 2)))?;
+        let ab_var = a_var.mul_no_carry(&b_var)?;
+        let r1 = ab_var.rem(&nn_var, &(BigUint::one() << (N * 2)))?;
+        let r2 = ab_var.rem_auto(&nn_var)?;
+        r1.enforce_lt(&nn_var)?;
+        r2.enforce_lt(&nn_var)?;
+        assert_eq!(r1.value()?, &a * &b % &nn);
+        assert_eq!(r2.value()?, r1.value()?);
+        assert!(cs.is_satisfied()?);
+
+        let m = rng.gen_biguint((N / 2) as u64);
+        let x = rng.gen_biguint((N * 2) as u64);
+        for auto in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), N)))?;
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N * 2)))?;
+            if auto {
+                assert_eq!(x_var.rem_auto(&m_var)?.value()?, &x % &m);
+            } else {
+                x_var.rem(&m_var, &(BigUint::one() << (N - 1)))?;
+            }
+            assert_eq!(cs.is_satisfied()?, auto);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_equal_unaligned() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint((N * 2) as u64);
+        for (y, satisfied) in
+            [(x.clone(), true), (&x ^ (BigUint::one() << rng.gen_range(0..N * 2)), false)]
+        {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N * 2)))?;
+            let y_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((y.clone(), N * 2)))?;
+            let before = cs.num_constraints();
+            x_var.enforce_equal_unaligned(&y_var)?;
+            // Both sides are normalized, so every group of limbs whose sum stays below
+            // (|F| - 1) / 2 is compared with a single constraint.
+            let group = (Fr::MODULUS_BIT_SIZE as usize - 2) / W;
+            assert_eq!(cs.num_constraints() - before, (N * 2 / W).div_ceil(group));
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        let a = rng.gen_biguint(N as u64);
+        let b = rng.gen_biguint(N as u64);
+        for (ab, satisfied) in [(&a * &b, true), (&a * &b + BigUint::one(), false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let a_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((a.clone(), N)))?;
+            let b_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((b.clone(), N)))?;
+            let ab_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((ab.clone(), N * 2)))?;
+            a_var.mul_no_carry(&b_var)?.enforce_equal_unaligned(&ab_var)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_double_halve() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let x = rng.gen_biguint(N as u64) << 1;
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N + 1)))?;
+        let d = x_var.double()?;
+        assert_eq!(d.value()?, &x << 1);
+        let h = d.halve()?.halve()?;
+        assert_eq!(h.value()?, &x >> 1);
+        assert!(cs.is_satisfied()?);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((&x + BigUint::one(), N + 1)))?;
+        x_var.halve()?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rem_reduced() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        // A constant modulus that is much shorter than its declared width.
+        let k = N * 2;
+        let m = (BigUint::one() << (k - 101)) + rng.gen_biguint((k - 132) as u64);
+        let x = rng.gen_biguint((k * 2) as u64);
+        let y = rng.gen_biguint(k as u64);
+
+        let mut counts = vec![];
+        for reduced in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let m_var = BigUintVar::<Fr, W>::constant(m.clone(), k)?;
+            let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), k * 2)))?;
+            let y_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((y.clone(), k)))?;
+            let before = cs.num_constraints();
+            let r = if reduced {
+                x_var.rem_reduced(&m_var, &m)?
+            } else {
+                let r = x_var.rem(&m_var, &m)?;
+                r.enforce_lt(&m_var)?;
+                r
+            };
+            assert_eq!(r.ubound().bits() <= m.bits(), reduced);
+            let z = r.mul_no_carry(&y_var)?.rem(&m_var, &m)?;
+            assert_eq!(z.value()?, &x % &m * &y % &m);
+            assert!(cs.is_satisfied()?);
+            counts.push(cs.num_constraints() - before);
+        }
+        assert!(counts[1] < counts[0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reduce_once() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(N as u64) | pow2_bound(N - 1);
+        for x in [
+            BigUint::zero(),
+            &m - BigUint::one(),
+            m.clone(),
+            &m * 2u32 - BigUint::one(),
+            rng.gen_biguint_below(&(&m * 2u32)),
+        ] {
+            let mut counts = vec![];
+            for once in [false, true] {
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), N)))?;
+                let x_var =
+                    BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N + 1)))?;
+                let before = cs.num_constraints();
+                let r = if once {
+                    x_var.reduce_once(&m_var)?
+                } else {
+                    x_var.rem_reduced(&m_var, &pow2_bound(N - 1))?
+                };
+                counts.push(cs.num_constraints() - before);
+                assert_eq!(r.value()?, &x % &m);
+                assert!(cs.is_satisfied()?);
+            }
+            assert!(counts[1] < counts[0]);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paillier_l() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+
+        for l in [BigUint::zero(), rng.gen_biguint_below(&n), rng.gen_biguint_below(&n)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || {
+                Ok((&l * &n + BigUint::one(), N * 2))
+            })?;
+            let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), N)))?;
+            assert_eq!(BigUintVar::paillier_l(&x, &n_var)?.value()?, l);
+            assert!(cs.is_satisfied()?);
+        }
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let l = rng.gen_biguint_below(&n);
+        let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || {
+            Ok((&l * &n + BigUint::from(2u32), N * 2))
+        })?;
+        let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), N)))?;
+        BigUintVar::paillier_l(&x, &n_var)?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+
+    // A decryption m = L(c^lambda mod n^2) * mu mod n, where n, lambda and mu are secret and
+    // only n^2 is public.
+    fn secret_decryption(
+        bits: usize,
+        n: &BigUint,
+        lambda: &BigUint,
+        mu: &BigUint,
+        c: &BigUint,
+        m: &BigUint,
+    ) -> Result<ConstraintSystemRef<Fr>, SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n * n, bits * 2)))?;
+        let c_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((c.clone(), bits * 2)))?;
+        let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), bits)))?;
+        let n_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((n.clone(), bits)))?;
+        let lambda_var =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((lambda.clone(), bits)))?;
+        let mu_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((mu.clone(), bits)))?;
+
+        let n_lbound = n_var.enforce_secret_modulus(bits)?;
+        n_var.mul_no_carry(&n_var)?.enforce_equal_unaligned(&nn_var)?;
+        let u = c_var.powm(&lambda_var.to_bits_le()?, &nn_var, &pow2_bound(bits * 2 - 2))?;
+        BigUintVar::paillier_l(&u, &n_var)?
+            .mul_no_carry(&mu_var)?
+            .rem_reduced(&n_var, &n_lbound)?
+            .enforce_equal_unaligned(&m_var)?;
+        Ok(cs)
+    }
+
+    #[test]
+    fn test_secret_modulus() -> Result<(), Box<dyn Error>> {
+        const N: usize = 256;
+        let rng = &mut thread_rng();
+        let mut shapes = vec![];
+        for _ in 0..2 {
+            let (pk, p, q) = crate::paillier::reference::keygen(N, rng);
+            let m = rng.gen_biguint_below(&pk.n);
+            let r = crate::paillier::native::sample_randomness(&pk, rng);
+            let c = crate::paillier::native::encrypt_with_randomness(&pk, &m, &r).0;
+            let lambda = (&p - BigUint::one()).lcm(&(&q - BigUint::one()));
+            let n = BigInt::from(pk.n.clone());
+            let mu = BigInt::from(lambda.clone()).extended_gcd(&n).x.mod_floor(&n);
+            let mu = mu.to_biguint().unwrap();
+
+            let cs = secret_decryption(N, &pk.n, &lambda, &mu, &c, &m)?;
+            assert!(cs.is_satisfied()?);
+            shapes.push((cs.num_constraints(), cs.num_witness_variables()));
+
+            let m = (m + BigUint::one()) % &pk.n;
+            assert!(!secret_decryption(N, &pk.n, &lambda, &mu, &c, &m)?.is_satisfied()?);
+        }
+        assert_eq!(shapes[0], shapes[1]);
+
+        // A modulus shorter than declared is rejected.
+        for (n, satisfied) in
+            [(pow2_bound(N - 1), true), (pow2_bound(N - 1) - BigUint::one(), false)]
+        {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let n_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((n, N)))?;
+            n_var.enforce_secret_modulus(N)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lcm() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let g = rng.gen_biguint(40) | BigUint::one();
+        for (a, b) in [
+            (&g * rng.gen_biguint(60), &g * rng.gen_biguint(60)),
+            (rng.gen_biguint(100), rng.gen_biguint(100)),
+            (g.clone(), g.clone()),
+            (BigUint::one(), g.clone()),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let a_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((a.clone(), 100)))?;
+            let b_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((b.clone(), 100)))?;
+            assert_eq!(a_var.lcm(&b_var)?.value()?, a.lcm(&b));
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_modulus_sane() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let n = crate::paillier::reference::keygen(N, rng).0.n;
+        // The largest odd multiple of k not above n, which still has N bits.
+        let odd_multiple = |k: u32| {
+            let x = &n / k * k;
+            if x.is_odd() {
+                x
+            } else {
+                x - k
+            }
+        };
+        for (n, satisfied) in [
+            (n.clone(), true),
+            (&n - BigUint::one(), false),
+            (odd_multiple(3), false),
+            (odd_multiple(47), false),
+            (rng.gen_prime_exact(N, None), true),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n, N)))?;
+            BigUintVar::enforce_modulus_sane(&n_var)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // Too short for its width.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), N + 1)))?;
+        BigUintVar::enforce_modulus_sane(&n_var)?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_limbs() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let (ark, mds) = find_poseidon_ark_and_mds::<Fr>(Fr::MODULUS_BIT_SIZE as u64, 2, 8, 57, 0);
+        let config = PoseidonConfig::new(8, 57, 5, mds, ark, 2, 1);
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &config);
+        sponge.absorb(&FpVar::new_witness(cs.clone(), || Ok(Fr::rand(rng)))?)?;
+        let h = sponge.squeeze_field_elements(1)?.pop().unwrap();
+        let limbs = h
+            .to_bits_le()?
+            .chunks(W)
+            .map(Boolean::le_bits_to_fp_var)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let b = BigUintVar::<Fr, W>::from_limbs(limbs, W)?;
+        assert_eq!(b.value()?, Into::<BigUint>::into(h.value()?));
+
+        let m = BigUintVar::<Fr, W>::constant(rng.gen_biguint(N as u64) | BigUint::one(), N)?;
+        let e = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(64), 64)))?;
+        assert_eq!(
+            b.value()?.modpow(&e.value()?, &m.value()?),
+            b.powm_const(&e.to_bits_le()?, &m)?.value()?
+        );
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_powm() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(N as u64);
+        let g = rng.gen_biguint_below(&m);
+        let h = rng.gen_biguint_below(&m);
+        let a = rng.gen_biguint(256);
+        let b = rng.gen_biguint(128);
+        let bound = BigUint::one() << N;
+
+        let alloc = |cs: ConstraintSystemRef<Fr>| -> Result<_, SynthesisError> {
+            Ok((
+                BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), N)))?,
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((g.clone(), N)))?,
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((h.clone(), N)))?,
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((a.clone(), 256)))?,
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((b.clone(), 128)))?,
+            ))
+        };
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let (m_var, g_var, h_var, a_var, b_var) = alloc(cs.clone())?;
+        let r = BigUintVar::multi_powm(
+            &[g_var, h_var],
+            &[a_var.to_bits_le()?, b_var.to_bits_le()?],
+            &m_var,
+            &bound,
+        )?;
+        assert_eq!(r.value()?, g.modpow(&a, &m) * h.modpow(&b, &m) % &m);
+        assert!(cs.is_satisfied()?);
+
+        let separate = ConstraintSystem::<Fr>::new_ref();
+        let (m_var, g_var, h_var, a_var, b_var) = alloc(separate.clone())?;
+        let r = g_var
+            .powm(&a_var.to_bits_le()?, &m_var, &bound)?
+            .mul_no_carry(&h_var.powm(&b_var.to_bits_le()?, &m_var, &bound)?)?
+            .rem(&m_var, &bound)?;
+        r.enforce_lt(&m_var)?;
+        assert!(separate.is_satisfied()?);
+
+        // Interleaving shares the squarings of the shorter exponent with the longer one.
+        assert!(
+            cs.num_constraints() < separate.num_constraints(),
+            "interleaved {} vs sequential {}",
+            cs.num_constraints(),
+            separate.num_constraints()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_zero_exponent() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let m = rng.gen_biguint(N as u64) | BigUint::one();
+        let x = rng.gen_biguint_below(&m);
+        let bound = BigUint::one();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), N)))?;
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), N)))?;
+        let zero = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::zero(), 64)))?;
+        for e in [vec![], vec![Boolean::FALSE; 64], zero.to_bits_le()?] {
+            assert_eq!(x_var.clone().powm(&e, &m_var, &bound)?.value()?, BigUint::one());
+            let r = BigUintVar::multi_powm(
+                &[x_var.clone(), x_var.clone()],
+                &[e.clone(), e.clone()],
+                &m_var,
+                &bound,
+            )?;
+            assert_eq!(r.value()?, BigUint::one());
+        }
+        assert_eq!(
+            x_var.powm_public_exp(&BigUint::zero(), &m_var, &bound)?.value()?,
+            BigUint::one()
+        );
+        assert!(cs.is_satisfied()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_debug() -> Result<(), Box<dyn Error>> {
+        let x = BigUint::from(0xdeadbeefcafeu64);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 64)))?;
+        let s = format!("{:?}", x_var);
+        assert!(s.contains("0xdeadbeefcafe"));
+        assert!(s.contains("limbs: 2"));
+        assert!(s.contains("bits: 64"));
+        assert_eq!(x_var.to_string(), "0xdeadbeefcafe");
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        cs.set_mode(SynthesisMode::Setup);
+        let x_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), 64)))?;
+        let s = format!("{:?}", x_var);
+        assert!(!s.contains("value"));
+        assert!(s.contains("limbs: 2"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_crt_combine() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let p_inv = p.modpow(&(&q - BigUint::from(2u32)), &q);
+        let x = rng.gen_biguint_below(&(&p * &q));
+
+        for (xp, satisfied) in [(&x % &p, true), (&x % &p + &p, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let xp = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((xp, N / 2 + 1)))?;
+            let xq = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((&x % &q, N / 2)))?;
+            let p_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((p.clone(), N / 2)))?;
+            let q_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((q.clone(), N / 2)))?;
+            let p_inv =
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((p_inv.clone(), N / 2)))?;
+
+            let r = BigUintVar::crt_combine(&xp, &xq, &p_var, &q_var, &p_inv)?;
+            if satisfied {
+                assert_eq!(r.value()?, x);
+            }
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_powm_shape() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let mut shapes = vec![];
+        for setup in [false, false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            if setup {
+                cs.set_mode(SynthesisMode::Setup);
+            }
+            let m = rng.gen_biguint(N as u64);
+            let b = rng.gen_biguint_below(&m);
+            let e = rng.gen_biguint(256);
+            let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), N)))?;
+            let b_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((b.clone(), N)))?;
+            let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((e.clone(), 256)))?;
+            let start = Instant::now();
+            let r = b_var.powm(&e_var.to_bits_le()?, &m_var, &(BigUint::one() << N))?;
+            println!("powm synthesis (setup: {}): {:?}", setup, start.elapsed());
+            if !setup {
+                assert_eq!(r.value()?, b.modpow(&e, &m));
+                assert!(cs.is_satisfied()?);
+            }
+            cs.finalize();
+            let matrices = cs.to_matrices().unwrap();
+            shapes.push((
+                cs.num_constraints(),
+                cs.num_witness_variables(),
+                cs.num_instance_variables(),
+                matrices.a,
+                matrices.b,
+                matrices.c,
+            ));
+        }
+        assert!(shapes.windows(2).all(|w| w[0] == w[1]));
+
+        // The (constraints, witnesses) of each gadget for 64-bit operands, as before the limbs
+        // were shared. rem range-checks a 64-bit q and r, multiplies q * m and carries once.
+        // powm with a 2-bit exponent selects twice, squares once and multiplies once.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m = rng.gen_biguint_range(&(BigUint::one() << 63), &(BigUint::one() << 64));
+        let m_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((m.clone(), 64)))?;
+        let x_var =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint_below(&m), 64)))?;
+        let y_var =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint_below(&m), 64)))?;
+        let e_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((BigUint::from(3u8), 2)))?;
+        let e = e_var.to_bits_le()?;
+        let cost = |f: &mut dyn FnMut() -> Result<(), SynthesisError>| {
+            let before = (cs.num_constraints(), cs.num_witness_variables());
+            f().map(|_| (cs.num_constraints() - before.0, cs.num_witness_variables() - before.1))
+        };
+        let xy = x_var.mul_no_carry(&y_var)?;
+        assert_eq!(cost(&mut || x_var.mul_no_carry(&y_var).map(|_| ()))?, (3, 3));
+        assert_eq!(cost(&mut || xy.rem(&m_var, &pow2_bound(64)).map(|_| ()))?, (137, 136));
+        let mut r = None;
+        let powm = cost(&mut || {
+            r = Some(x_var.clone().powm(&e, &m_var, &pow2_bound(64))?);
+            Ok(())
+        })?;
+        // Without the final range check, which costs the same again.
+        let lt = cost(&mut || r.as_ref().unwrap().enforce_lt(&m_var))?;
+        assert_eq!((powm.0 - lt.0, powm.1 - lt.1), (284, 282));
+        assert_eq!(cs.num_instance_variables(), 3);
+        assert!(cs.is_satisfied()?);
+
+        // Cloning shares the limbs instead of copying them, which is what `powm` does for
+        // every selection and product.
+        let x_var =
+            BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(2048), 2048)))?;
+        let time = |f: &dyn Fn() -> BigUintVar<Fr, W>| {
+            let start = Instant::now();
+            for _ in 0..10000 {
+                std::hint::black_box(f());
+            }
+            start.elapsed()
+        };
+        let shared = time(&|| x_var.clone());
+        let copied = time(&|| BigUintVar(x_var.0.iter().cloned().collect()));
+        assert!(shared < copied, "{:?} vs {:?}", shared, copied);
+
+        Ok(())
+    }
+
     // #[test]
     // fn find() -> Result<(), Box<dyn Error>> {
     //     let rng = &mut thread_rng();
@@ -0,0 +1,138 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, R1CSVar, ToBitsGadget};
+use ark_relations::r1cs::SynthesisError;
+use num::BigUint;
+
+use super::BigUintVar;
+
+// The operations the Paillier gadgets need from a non-native bignum representation. Values
+// are allocated from `(value, bit length)` pairs, and `m_lbound` is a public lower bound on
+// the modulus, as in `BigUintVar::rem`.
+pub trait BigUintOps<F: PrimeField>:
+    Sized + Clone + R1CSVar<F, Value = BigUint> + AllocVar<(BigUint, usize), F> + ToBitsGadget<F>
+{
+    fn constant(v: BigUint, bits: usize) -> Result<Self, SynthesisError>;
+
+    fn inputize(x: &BigUint, bits: usize) -> Vec<F>;
+
+    fn mul_mod(&self, other: &Self, m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError>;
+
+    // The result is enforced to be below m.
+    fn powm(&self, e: &[Boolean<F>], m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError>;
+
+    // The result is enforced to be below m.
+    fn multi_powm(
+        bases: &[Self],
+        exps: &[Vec<Boolean<F>>],
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        assert_eq!(bases.len(), exps.len());
+        let mut r = bases[0].powm(&exps[0], m, m_lbound)?;
+        for (base, e) in bases.iter().zip(exps).skip(1) {
+            r = r.mul_mod(&base.powm(e, m, m_lbound)?, m, m_lbound)?;
+        }
+        if bases.len() > 1 {
+            r.enforce_lt(m)?;
+        }
+        Ok(r)
+    }
+
+    // Equality of the represented integers, regardless of how they are represented.
+    fn enforce_equal(&self, other: &Self) -> Result<(), SynthesisError>;
+
+    fn enforce_lt(&self, other: &Self) -> Result<(), SynthesisError>;
+}
+
+impl<F: PrimeField, const W: usize> BigUintOps<F> for BigUintVar<F, W> {
+    fn constant(v: BigUint, bits: usize) -> Result<Self, SynthesisError> {
+        BigUintVar::constant(v, bits)
+    }
+
+    fn inputize(x: &BigUint, bits: usize) -> Vec<F> {
+        BigUintVar::<F, W>::inputize(x, bits)
+    }
+
+    fn mul_mod(&self, other: &Self, m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError> {
+        BigUintVar::rem(&BigUintVar::mul_no_carry(self, other)?, m, m_lbound)
+    }
+
+    fn powm(&self, e: &[Boolean<F>], m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError> {
+        BigUintVar::powm(self.clone(), e, m, m_lbound)
+    }
+
+    fn multi_powm(
+        bases: &[Self],
+        exps: &[Vec<Boolean<F>>],
+        m: &Self,
+        m_lbound: &BigUint,
+    ) -> Result<Self, SynthesisError> {
+        BigUintVar::multi_powm(bases, exps, m, m_lbound)
+    }
+
+    fn enforce_equal(&self, other: &Self) -> Result<(), SynthesisError> {
+        BigUintVar::enforce_equal_unaligned(self, other)
+    }
+
+    fn enforce_lt(&self, other: &Self) -> Result<(), SynthesisError> {
+        BigUintVar::enforce_lt(self, other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::PaillierEncGadget;
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_gadget_cost() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let n = rng.gen_biguint(N as u64) | BigUint::one();
+        let nn = &n * &n;
+        let g = &n + BigUint::one();
+        let m = rng.gen_biguint_below(&n);
+        let r = rng.gen_biguint_below(&n);
+        let nn_lbound = BigUint::one() << (N * 2 - 2);
+
+        let mut counts = vec![];
+        for generic in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let alloc = |x: &BigUint, bits| {
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((x.clone(), bits)))
+            };
+            let (g_var, n_var, nn_var, m_var, r_var) = (
+                alloc(&g, N * 2)?,
+                alloc(&n, N)?,
+                alloc(&nn, N * 2)?,
+                alloc(&m, N)?,
+                alloc(&r, N)?,
+            );
+            let c = if generic {
+                PaillierEncGadget::encrypt(&g_var, &n_var, &nn_var, &m_var, &r_var, &nn_lbound)?
+            } else {
+                BigUintVar::multi_powm(
+                    &[g_var, r_var],
+                    &[m_var.to_bits_le()?, n_var.to_bits_le()?],
+                    &nn_var,
+                    &nn_lbound,
+                )?
+            };
+            assert_eq!(c.value()?, g.modpow(&m, &nn) * r.modpow(&n, &nn) % &nn);
+            assert!(cs.is_satisfied()?);
+            counts.push(cs.num_constraints());
+        }
+        assert_eq!(counts[0], counts[1]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,318 @@
+use std::cmp::max;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    fields::{fp::FpVar, FieldVar},
+    prelude::EqGadget,
+    R1CSVar,
+};
+use ark_relations::{
+    ns,
+    r1cs::{ConstraintSystemRef, SynthesisError},
+};
+use num::{BigInt, BigUint, Integer, One, Signed, Zero};
+
+use super::BigUintVar;
+
+// Unsigned integers x = sum_i x_i * base^i for an arbitrary base, e.g., one that divides the
+// modulus into whole limbs. Every limb carries an upper bound on its value, as in
+// `BigUintVar`, which is the special case base = 2^W and remains the default. A fresh limb
+// below a base that is not a power of two needs two range checks, one for x_i and one for
+// base - 1 - x_i.
+#[derive(Clone)]
+pub struct RadixUintVar<F: PrimeField> {
+    pub base: BigUint,
+    pub limbs: Vec<(FpVar<F>, BigUint)>,
+}
+
+fn to_fp<F: PrimeField>(x: &BigInt) -> F {
+    let f = F::from(x.magnitude().clone());
+    if x.is_negative() {
+        -f
+    } else {
+        f
+    }
+}
+
+impl<F: PrimeField> RadixUintVar<F> {
+    // The lowest `len` digits of x in the base.
+    fn digits(x: &BigUint, base: &BigUint, len: usize) -> Vec<BigUint> {
+        let mut x = x.clone();
+        (0..len)
+            .map(|_| {
+                let (q, r) = x.div_rem(base);
+                x = q;
+                r
+            })
+            .collect()
+    }
+
+    // The number of digits needed for values up to `ubound`.
+    fn len_for(ubound: &BigUint, base: &BigUint) -> usize {
+        let mut len = 1;
+        let mut p = base.clone();
+        while &p <= ubound {
+            p *= base;
+            len += 1;
+        }
+        len
+    }
+
+    fn enforce_limb(limb: &FpVar<F>, base: &BigUint) -> Result<(), SynthesisError> {
+        let max_digit = base - BigUint::one();
+        let bits = max_digit.bits() as usize;
+        BigUintVar::<F, 1>::to_bit_array(limb, bits)?;
+        if base.count_ones() != 1 {
+            BigUintVar::<F, 1>::to_bit_array(&(FpVar::constant(F::from(max_digit)) - limb), bits)?;
+        }
+        Ok(())
+    }
+
+    // Allocates the lowest `len` digits of x as fresh limbs, like `BigUintVar::new_witness`.
+    pub fn new_witness(
+        cs: ConstraintSystemRef<F>,
+        x: &BigUint,
+        len: usize,
+        base: BigUint,
+    ) -> Result<Self, SynthesisError> {
+        assert!(base > BigUint::one());
+        let limbs = Self::digits(x, &base, len)
+            .into_iter()
+            .map(|d| {
+                let limb = FpVar::new_witness(cs.clone(), || Ok(F::from(d)))?;
+                Self::enforce_limb(&limb, &base)?;
+                Ok((limb, &base - BigUint::one()))
+            })
+            .collect::<Result<_, SynthesisError>>()?;
+        Ok(Self { base, limbs })
+    }
+
+    pub fn constant(x: &BigUint, len: usize, base: BigUint) -> Self {
+        assert!(base > BigUint::one());
+        let limbs = Self::digits(x, &base, len)
+            .into_iter()
+            .map(|d| (FpVar::constant(F::from(d.clone())), d))
+            .collect();
+        Self { base, limbs }
+    }
+
+    pub fn cs(&self) -> ConstraintSystemRef<F> {
+        self.limbs.iter().fold(ConstraintSystemRef::None, |cs, limb| cs.or(limb.0.cs()))
+    }
+
+    pub fn is_constant(&self) -> bool {
+        self.limbs.iter().all(|limb| limb.0.is_constant())
+    }
+
+    pub fn value(&self) -> Result<BigUint, SynthesisError> {
+        self.limbs.iter().rev().try_fold(BigUint::zero(), |acc, limb| {
+            Ok(acc * &self.base + BigUint::from(limb.0.value()?.into_bigint()))
+        })
+    }
+
+    pub fn ubound(&self) -> BigUint {
+        self.limbs.iter().rev().fold(BigUint::zero(), |acc, limb| acc * &self.base + &limb.1)
+    }
+
+    fn check_bound(ubound: &BigUint) {
+        assert!(ubound < &F::MODULUS_MINUS_ONE_DIV_TWO.into(), "limb overflows the field");
+    }
+
+    pub fn add_no_carry(&self, other: &Self) -> Self {
+        assert_eq!(self.base, other.base);
+        let mut limbs =
+            vec![(FpVar::zero(), BigUint::zero()); max(self.limbs.len(), other.limbs.len())];
+        for x in [self, other] {
+            for (z, limb) in limbs.iter_mut().zip(&x.limbs) {
+                *z = (&z.0 + &limb.0, &z.1 + &limb.1);
+                Self::check_bound(&z.1);
+            }
+        }
+        Self { base: self.base.clone(), limbs }
+    }
+
+    // The product limbs are checked by evaluating both sides at 1, 2, ..., len, as in
+    // `BigUintVar::mul_no_carry`, which does not depend on the base.
+    pub fn mul_no_carry(&self, other: &Self) -> Result<Self, SynthesisError> {
+        assert_eq!(self.base, other.base);
+        let len = self.limbs.len() + other.limbs.len() - 1;
+        let mut ubounds = vec![BigUint::zero(); len];
+        for (i, x) in self.limbs.iter().enumerate() {
+            for (j, y) in other.limbs.iter().enumerate() {
+                ubounds[i + j] += &x.1 * &y.1;
+            }
+        }
+        ubounds.iter().for_each(Self::check_bound);
+
+        if self.is_constant() || other.is_constant() {
+            let mut z = vec![FpVar::zero(); len];
+            for (i, x) in self.limbs.iter().enumerate() {
+                for (j, y) in other.limbs.iter().enumerate() {
+                    z[i + j] += &x.0 * &y.0;
+                }
+            }
+            return Ok(Self {
+                base: self.base.clone(),
+                limbs: z.into_iter().zip(ubounds).collect(),
+            });
+        }
+        let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "mul_no_carry");
+
+        let mut z = vec![F::zero(); len];
+        for (i, x) in self.limbs.iter().enumerate() {
+            for (j, y) in other.limbs.iter().enumerate() {
+                z[i + j] += x.0.value().unwrap_or_default() * y.0.value().unwrap_or_default();
+            }
+        }
+        let z = z
+            .into_iter()
+            .map(|f| FpVar::new_witness(cs.clone(), || Ok(f)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let eval = |limbs: Vec<&FpVar<F>>, c: usize| {
+            let mut t = F::one();
+            let mut acc = FpVar::<F>::zero();
+            for limb in limbs {
+                acc += limb * t;
+                t *= F::from(c as u64);
+            }
+            acc
+        };
+        for c in 1..=len {
+            let l = eval(self.limbs.iter().map(|limb| &limb.0).collect(), c);
+            let r = eval(other.limbs.iter().map(|limb| &limb.0).collect(), c);
+            l.mul_equals(&r, &eval(z.iter().collect(), c))?;
+        }
+
+        Ok(Self { base: self.base.clone(), limbs: z.into_iter().zip(ubounds).collect() })
+    }
+
+    // Equality of the represented integers. The limb differences are carried in the base:
+    // x_i - y_i + c_(i-1) = c_i * base, where the signed carries c_i are range-checked after
+    // an offset by their bound, and the last one is zero.
+    pub fn enforce_equal_unaligned(&self, other: &Self) -> Result<(), SynthesisError> {
+        assert_eq!(self.base, other.base);
+        let cs = self.cs().or(other.cs());
+        let _ns = ns!(cs, "enforce_equal_unaligned");
+        let base = &self.base;
+        let len = max(self.limbs.len(), other.limbs.len());
+        let zero = (FpVar::zero(), BigUint::zero());
+
+        let mut carry = FpVar::<F>::zero();
+        let mut carry_value = BigInt::zero();
+        let mut carry_bound = BigUint::zero();
+        for i in 0..len {
+            let x = self.limbs.get(i).unwrap_or(&zero);
+            let y = other.limbs.get(i).unwrap_or(&zero);
+            let t = &x.0 - &y.0 + &carry;
+            if i == len - 1 {
+                t.enforce_equal(&FpVar::zero())?;
+                break;
+            }
+
+            let bound = (max(&x.1, &y.1) + &carry_bound) / base;
+            let value = |v: &FpVar<F>| {
+                BigInt::from(BigUint::from(v.value().unwrap_or_default().into_bigint()))
+            };
+            carry_value =
+                (value(&x.0) - value(&y.0) + &carry_value).div_floor(&BigInt::from(base.clone()));
+            carry = if cs.is_none() {
+                FpVar::constant(to_fp(&carry_value))
+            } else {
+                FpVar::new_witness(cs.clone(), || Ok(to_fp(&carry_value)))?
+            };
+            let offset = FpVar::constant(F::from(bound.clone()));
+            BigUintVar::<F, 1>::to_bit_array(&(&carry + offset), (&bound * 2u32).bits() as usize)?;
+            t.enforce_equal(&(&carry * F::from(base.clone())))?;
+            carry_bound = bound;
+        }
+
+        Ok(())
+    }
+
+    // Same as `BigUintVar::rem`: returns r with q * m + r = self, where r has as many digits
+    // as m but is not enforced to be below m.
+    pub fn rem(&self, m: &Self, m_lbound: &BigUint) -> Result<Self, SynthesisError> {
+        assert_eq!(self.base, m.base);
+        let cs = self.cs().or(m.cs());
+        let _ns = ns!(cs, "rem");
+        let base = &self.base;
+        let (q, r) = self.value().unwrap_or_default().div_rem(&m.value().unwrap_or(BigUint::one()));
+        let q_len = Self::len_for(&self.ubound().div_ceil(m_lbound), base);
+        let r_len = Self::len_for(&m.ubound(), base);
+        let (q, r) = if cs.is_none() {
+            (Self::constant(&q, q_len, base.clone()), Self::constant(&r, r_len, base.clone()))
+        } else {
+            (
+                Self::new_witness(cs.clone(), &q, q_len, base.clone())?,
+                Self::new_witness(cs.clone(), &r, r_len, base.clone())?,
+            )
+        };
+        q.mul_no_carry(m)?.add_no_carry(&r).enforce_equal_unaligned(self)?;
+        Ok(r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::bigint::RandBigInt;
+    use rand::thread_rng;
+
+    use super::*;
+
+    #[test]
+    fn test_radix() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let base = BigUint::from(10u32).pow(9);
+        let len = 8;
+        let bound = base.pow(len as u32);
+        let m = rng.gen_biguint_range(&(&bound / 2u32), &bound);
+        let x = rng.gen_biguint_below(&m);
+        let y = rng.gen_biguint_below(&m);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let m_var = RadixUintVar::new_witness(cs.clone(), &m, len, base.clone())?;
+        let x_var = RadixUintVar::new_witness(cs.clone(), &x, len, base.clone())?;
+        let y_var = RadixUintVar::new_witness(cs.clone(), &y, len, base.clone())?;
+        assert_eq!(x_var.value()?, x);
+
+        let z_var = x_var.mul_no_carry(&y_var)?;
+        assert_eq!(z_var.value()?, &x * &y);
+        let s_var = x_var.add_no_carry(&y_var);
+        assert_eq!(s_var.value()?, &x + &y);
+        let r_var = z_var.rem(&m_var, &(&bound / 2u32))?;
+        assert_eq!(r_var.value()?, &x * &y % &m);
+        assert!(cs.is_satisfied()?);
+
+        // A digit equal to the base is rejected, although it fits in the same number of bits.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let limb = FpVar::new_witness(cs.clone(), || Ok(Fr::from(base.clone())))?;
+        RadixUintVar::<Fr>::enforce_limb(&limb, &base)?;
+        assert!(!cs.is_satisfied()?);
+
+        // Equality is of the represented integers: a single limb `base` equals the digits
+        // (0, 1), but not (1, 1).
+        for (y, satisfied) in [(base.clone(), true), (&base + 1u32, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let one =
+                RadixUintVar::<Fr>::new_witness(cs.clone(), &BigUint::one(), 1, base.clone())?;
+            let unnormalized = RadixUintVar::<Fr> {
+                base: base.clone(),
+                limbs: vec![(FpVar::constant(Fr::from(base.clone())), base.clone())],
+            };
+            one.mul_no_carry(&unnormalized)?.enforce_equal_unaligned(&RadixUintVar::constant(
+                &y,
+                2,
+                base.clone(),
+            ))?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use ark_ff::PrimeField;
+use ark_relations::r1cs::{ConstraintLayer, ConstraintSystemRef, TracingMode};
+use tracing_subscriber::layer::SubscriberExt;
+
+// Runs f with a subscriber that records the namespaces every constraint is created in. The
+// gadgets open a namespace per operation, e.g., "powm", "square", "rem", so a constraint
+// created while squaring in a modular exponentiation is traced under "powm", "square" and
+// "rem".
+// Span names are static, so the path names the operations but not the loop indices.
+pub fn with_tracing<R>(f: impl FnOnce() -> R) -> R {
+    let mut layer = ConstraintLayer::default();
+    layer.mode = TracingMode::OnlyConstraints;
+    tracing::subscriber::with_default(tracing_subscriber::Registry::default().with(layer), f)
+}
+
+// The namespace path of the first unsatisfied constraint, or None if all are satisfied. The
+// constraints have to be generated inside `with_tracing`, otherwise only the index is known.
+pub fn first_unsatisfied_path<F: PrimeField>(cs: &ConstraintSystemRef<F>) -> Option<String> {
+    cs.which_is_unsatisfied().ok().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_ff::One;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_relations::r1cs::{ConstraintSystem, SynthesisError};
+    use num::{bigint::RandBigInt, BigUint};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::bn::BigUintVar;
+
+    const W: usize = 32;
+
+    #[test]
+    fn test_first_unsatisfied_path() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let q_index = with_tracing(|| -> Result<_, SynthesisError> {
+            let x =
+                BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((rng.gen_biguint(512), 512)))?;
+            let m = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((rng.gen_biguint(256), 256)))?;
+            // The first witness allocated by `rem` is the lowest limb of the quotient.
+            let q_index = cs.num_witness_variables();
+            x.rem(&m, &BigUint::one())?;
+            Ok(q_index)
+        })?;
+        assert_eq!(first_unsatisfied_path(&cs), None);
+
+        cs.borrow_mut().unwrap().witness_assignment[q_index] += Fr::one();
+        let path = first_unsatisfied_path(&cs).unwrap();
+        assert!(path.contains("rem") && path.contains("quotient_rangecheck"), "{}", path);
+
+        Ok(())
+    }
+}
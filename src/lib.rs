@@ -1,133 +1,134 @@
-use std::error::Error;
-
-use ark_bn254::{Bn254, Fr};
-use ark_ff::{One, PrimeField};
-use ark_groth16::{
-    create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
-};
-use ark_r1cs_std::{
-    prelude::{AllocVar, ToBitsGadget},
-    R1CSVar,
-};
-use ark_relations::r1cs::{
-    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
-};
-use ark_serialize::CanonicalSerialize;
-use bn::BigUintVar;
-use num::{bigint::RandBigInt, BigUint};
-use num_prime::RandPrime;
-use rand::thread_rng;
-
-mod bn;
-
-const W: usize = 32;
-const N: usize = 1024;
-
-struct TestCircuit {
-    m: BigUint,
-    n: BigUint,
-    r: BigUint,
-    c: BigUint,
-}
+pub mod bn;
+// The tracing layer of ark-relations needs std.
+#[cfg(feature = "std")]
+pub mod debug;
+pub mod paillier;
+
+// The size of n in the tests that would otherwise use a realistic 1024-bit key. `fast-tests`
+// brings it down to 256 bits for quicker iteration, which is insecure, but the circuits are
+// the same apart from their size.
+#[cfg(test)]
+const TEST_BITS: usize = if cfg!(feature = "fast-tests") { 256 } else { 1024 };
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_ff::One;
+    use ark_groth16::{create_random_proof, generate_random_parameters};
+    use ark_r1cs_std::{
+        prelude::{AllocVar, ToBitsGadget},
+        R1CSVar,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use ark_serialize::CanonicalSerialize;
+    use num::{bigint::RandBigInt, BigUint};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use crate::{
+        bn::{pow2_bound, BigUintVar},
+        paillier::{groth16::PaillierVerifier, PaillierEncCircuit},
+    };
+
+    const W: usize = 32;
+    const N: usize = crate::TEST_BITS;
+
+    fn encryption(bits: usize) -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(bits / 2, None);
+        let q: BigUint = rng.gen_prime_exact(bits / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let r = rng.gen_biguint_below(&n);
+        let m = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+
+        let cs = ConstraintSystem::new_ref();
+
+        let m_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((m.clone(), bits)))?;
+        let g_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((g.clone(), bits * 2)))?;
+        let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), bits)))?;
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), bits * 2)))?;
+        let r_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((r.clone(), bits * 2)))?;
+
+        let c_var = g_var
+            .powm(&m_var.to_bits_le()?, &nn_var, &pow2_bound(bits * 2))?
+            .mul_no_carry(&r_var.powm(&n_var.to_bits_le()?, &nn_var, &pow2_bound(bits * 2))?)?
+            .rem(&nn_var, &pow2_bound(bits * 2))?;
+        c_var.enforce_lt(&nn_var)?;
+
+        assert_eq!(
+            c_var.value()?,
+            (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn
+        );
+        println!("{}", cs.num_constraints());
+        assert!(cs.is_satisfied()?);
+
+        Ok(())
+    }
 
-impl<F: PrimeField> ConstraintSynthesizer<F> for TestCircuit {
-    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
-        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, N)))?;
-        let nn_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((&self.n * &self.n, N * 2)))?;
-        let g_var =
-            BigUintVar::<F, W>::new_input(cs.clone(), || Ok((&self.n + BigUint::one(), N * 2)))?;
-        let n_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.n, N)))?;
-        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, N * 2)))?;
-        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, N * 2)))?;
-
-        g_var
-            .powm(&m_var.to_bits_le()?, &nn_var, &(BigUint::one() << (N * 2)))?
-            .mul_no_carry(&r_var.powm(
-                &n_var.to_bits_le()?,
-                &nn_var,
-                &(BigUint::one() << (N * 2)),
-            )?)?
-            .rem(&nn_var, &(BigUint::one() << (N * 2)))?
-            .enforce_equal_unaligned(&c_var)?;
+    fn groth16(bits: usize) -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(bits / 2, None);
+        let q: BigUint = rng.gen_prime_exact(bits / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let r = rng.gen_biguint_below(&n);
+        let m = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+
+        let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+
+        let pk = generate_random_parameters::<Bn254, _, _>(
+            PaillierEncCircuit::<W>::new(
+                bits,
+                Default::default(),
+                rng.gen_biguint_range(&pow2_bound(bits), &pow2_bound(bits + 1)),
+                Default::default(),
+                Default::default(),
+            ),
+            rng,
+        )?;
+        println!("{}", pk.compressed_size());
+
+        let pi = create_random_proof(
+            PaillierEncCircuit::<W>::new(bits, m, n.clone(), r, c.clone()),
+            &pk,
+            rng,
+        )?;
+
+        let verifier = PaillierVerifier::<Bn254, W>::for_encryption(&pk.vk, bits, false);
+        let values = [nn, g, n, c];
+        // nn, g and c take 2 * bits / W limbs each, and n takes bits / W.
+        assert_eq!(verifier.inputs(&values).unwrap().len(), (bits * 7).div_ceil(W));
+        assert!(verifier.verify(&pi, &values)?);
 
         Ok(())
     }
-}
 
-#[test]
-fn test() -> Result<(), Box<dyn Error>> {
-    let rng = &mut thread_rng();
-    let p: BigUint = rng.gen_prime_exact(N / 2, None);
-    let q: BigUint = rng.gen_prime_exact(N / 2, None);
-    let n = &p * &q;
-    let nn = &n * &n;
-    let r = rng.gen_biguint_below(&n);
-    let m = rng.gen_biguint_below(&n);
-    let g = &n + BigUint::one();
-
-    let cs = ConstraintSystem::new_ref();
-
-    let m_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((m.clone(), N)))?;
-    let g_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((g.clone(), N * 2)))?;
-    let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), N)))?;
-    let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
-    let r_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((r.clone(), N * 2)))?;
-
-    let c_var = g_var
-        .powm(&m_var.to_bits_le()?, &nn_var, &(BigUint::one() << (N * 2)))?
-        .mul_no_carry(&r_var.powm(&n_var.to_bits_le()?, &nn_var, &(BigUint::one() << (N * 2)))?)?
-        .rem(&nn_var, &(BigUint::one() << (N * 2)))?;
-    c_var.enforce_lt(&nn_var)?;
-
-    assert_eq!(
-        c_var.value()?,
-        (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn
-    );
-    println!("{}", cs.num_constraints());
-    assert!(cs.is_satisfied()?);
-
-    Ok(())
-}
+    #[test]
+    fn test() -> Result<(), Box<dyn Error>> {
+        encryption(N)
+    }
+
+    #[test]
+    fn test_groth16() -> Result<(), Box<dyn Error>> {
+        groth16(N)
+    }
 
-#[test]
-fn test_groth16() -> Result<(), Box<dyn Error>> {
-    let rng = &mut thread_rng();
-    let p: BigUint = rng.gen_prime_exact(N / 2, None);
-    let q: BigUint = rng.gen_prime_exact(N / 2, None);
-    let n = &p * &q;
-    let nn = &n * &n;
-    let r = rng.gen_biguint_below(&n);
-    let m = rng.gen_biguint_below(&n);
-    let g = &n + BigUint::one();
-
-    let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
-
-    let pk = generate_random_parameters::<Bn254, _, _>(
-        TestCircuit {
-            m: Default::default(),
-            n: rng.gen_biguint_range(&(BigUint::one() << N), &(BigUint::one() << N + 1)),
-            r: Default::default(),
-            c: Default::default(),
-        },
-        rng,
-    )?;
-    println!("{}", pk.compressed_size());
-
-    let vk = prepare_verifying_key(&pk.vk);
-
-    let pi = create_random_proof(TestCircuit { m, n: n.clone(), r, c: c.clone() }, &pk, rng)?;
-
-    assert!(verify_proof(
-        &vk,
-        &pi,
-        &vec![
-            BigUintVar::<Fr, W>::inputize(&nn, N * 2),
-            BigUintVar::<Fr, W>::inputize(&g, N * 2),
-            BigUintVar::<Fr, W>::inputize(&n, N),
-            BigUintVar::<Fr, W>::inputize(&c, N * 2),
-        ]
-        .concat()
-    )?);
-
-    Ok(())
+    // The size `fast-tests` runs everything at, so that it keeps working without the feature.
+    #[test]
+    #[cfg(not(feature = "fast-tests"))]
+    fn test_groth16_256() -> Result<(), Box<dyn Error>> {
+        encryption(256)?;
+        groth16(256)
+    }
+
+    #[test]
+    #[cfg(feature = "expensive-tests")]
+    fn test_groth16_2048() -> Result<(), Box<dyn Error>> {
+        groth16(2048)
+    }
 }
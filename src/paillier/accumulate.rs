@@ -0,0 +1,182 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    fields::{fp::FpVar, FieldVar},
+    prelude::EqGadget,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::{BigUint, One};
+
+use super::{
+    enforce_valid_ciphertext,
+    group::{ModularGroup, PaillierGroup},
+};
+use crate::bn::BigUintVar;
+
+// Step function of an incrementally verifiable computation in the style of Nova: the state
+// z is a vector of field elements, and each step maps z_in to z_out.
+pub trait StepCircuit<F: PrimeField> {
+    fn arity(&self) -> usize;
+
+    fn synthesize_step(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        z_in: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError>;
+}
+
+fn limbs_per_element<F: PrimeField, const W: usize>() -> usize {
+    (F::MODULUS_BIT_SIZE as usize - 1) / W
+}
+
+// Packs the W-bit limbs of x into as few field elements as possible, least significant first.
+pub fn pack<F: PrimeField, const W: usize>(x: &BigUint, bits: usize) -> Vec<F> {
+    BigUintVar::<F, W>::inputize_packed(x, bits)
+}
+
+// Requires the limbs of x to be normalized, so that the packing is unique.
+fn pack_var<F: PrimeField, const W: usize>(x: &BigUintVar<F, W>) -> Vec<FpVar<F>> {
+    x.0.chunks(limbs_per_element::<F, W>())
+        .map(|chunk| {
+            chunk
+                .iter()
+                .rev()
+                .fold(FpVar::zero(), |acc, limb| acc * F::from(BigUint::one() << W) + &limb.0)
+        })
+        .collect()
+}
+
+// One step of c_1 * ... * c_k mod n^2, i.e., of homomorphically adding up the plaintexts. The
+// state is the running ciphertext, and c is the ciphertext folded in by this step. With
+// `validate_ciphertexts`, c is also checked to be well-formed.
+#[derive(Clone)]
+pub struct HomomorphicAccumulateStep<const W: usize> {
+    pub bits: usize,
+    pub n: BigUint,
+    pub acc: BigUint,
+    pub c: BigUint,
+    pub validate_ciphertexts: bool,
+}
+
+impl<const W: usize> HomomorphicAccumulateStep<W> {
+    pub fn new(bits: usize, n: BigUint, acc: BigUint, c: BigUint) -> Self {
+        Self { bits, n, acc, c, validate_ciphertexts: false }
+    }
+
+    pub fn output(&self) -> BigUint {
+        &self.acc * &self.c % (&self.n * &self.n)
+    }
+}
+
+impl<F: PrimeField, const W: usize> StepCircuit<F> for HomomorphicAccumulateStep<W> {
+    fn arity(&self) -> usize {
+        (self.bits * 2).div_ceil(W).div_ceil(limbs_per_element::<F, W>())
+    }
+
+    fn synthesize_step(
+        &self,
+        cs: ConstraintSystemRef<F>,
+        z_in: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let bits = self.bits * 2;
+        let nn = BigUintVar::<F, W>::constant(&self.n * &self.n, bits)?;
+        let acc = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.acc.clone(), bits)))?;
+        pack_var(&acc).enforce_equal(z_in)?;
+        let c = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.c.clone(), bits)))?;
+        if self.validate_ciphertexts {
+            let n = BigUintVar::<F, W>::constant(self.n.clone(), self.bits)?;
+            enforce_valid_ciphertext(&c, &n, &nn)?;
+        }
+
+        let group = PaillierGroup::new(nn.clone(), nn.lbound()?);
+        Ok(pack_var(&group.mul(&acc, &c)?))
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for HomomorphicAccumulateStep<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let z_in =
+            Vec::<FpVar<F>>::new_input(cs.clone(), || Ok(pack::<F, W>(&self.acc, self.bits * 2)))?;
+        let out = pack::<F, W>(&self.output(), self.bits * 2);
+        let z_out = self.synthesize_step(cs.clone(), &z_in)?;
+        Vec::<FpVar<F>>::new_input(cs.clone(), || Ok(out))?.enforce_equal(&z_out)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::bigint::RandBigInt;
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::native::{encrypt_with_randomness, sample_randomness, PublicKey};
+
+    const W: usize = 32;
+    const N: usize = 128;
+
+    #[test]
+    fn test_accumulate() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, &p * &q);
+        let nn = pk.nn();
+
+        let mut acc = BigUint::one();
+        let mut z = pack::<Fr, W>(&acc, N * 2);
+        let mut sum = BigUint::from(0u32);
+        for _ in 0..10 {
+            let m = rng.gen_biguint_below(&pk.n);
+            let c = encrypt_with_randomness(&pk, &m, &sample_randomness(&pk, rng)).0;
+            sum = (sum + m) % &pk.n;
+
+            let step = HomomorphicAccumulateStep::<W>::new(N, pk.n.clone(), acc.clone(), c);
+            let arity = StepCircuit::<Fr>::arity(&step);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            step.clone().generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+
+            // The inputs of each step are the outputs of the previous one.
+            let instance = cs.borrow().unwrap().instance_assignment.clone();
+            assert_eq!(instance.len(), 1 + arity * 2);
+            assert_eq!(instance[1..1 + arity], z[..]);
+            z = instance[1 + arity..].to_vec();
+            acc = step.output();
+            assert_eq!(z, pack::<Fr, W>(&acc, N * 2));
+        }
+
+        // With g = n + 1, the accumulated ciphertext is (1 + sum * n) * r^n for some r.
+        let g_sum = (BigUint::one() + &sum * &pk.n) % &nn;
+        let lambda = (&p - BigUint::one()) * (&q - BigUint::one());
+        assert_eq!(acc.modpow(&lambda, &nn), g_sum.modpow(&lambda, &nn));
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let step = HomomorphicAccumulateStep::<W>::new(N, pk.n.clone(), acc.clone(), acc.clone());
+        let z_in = Vec::<FpVar<Fr>>::new_input(cs.clone(), || {
+            Ok(pack::<Fr, W>(&(&acc + BigUint::one()), N * 2))
+        })?;
+        step.synthesize_step(cs.clone(), &z_in)?;
+        assert!(!cs.is_satisfied()?);
+
+        // A ciphertext sharing the factor p with n is only rejected when validating.
+        let c = &p * rng.gen_biguint_below(&q);
+        for validate_ciphertexts in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            HomomorphicAccumulateStep::<W> {
+                validate_ciphertexts,
+                ..HomomorphicAccumulateStep::new(N, pk.n.clone(), acc.clone(), c.clone())
+            }
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, !validate_ciphertexts);
+        }
+
+        Ok(())
+    }
+}
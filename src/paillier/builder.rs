@@ -0,0 +1,215 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::PublicKeyVar;
+use crate::bn::BigUintVar;
+
+#[derive(Clone)]
+enum Component {
+    // c = Enc(m; r) under the key with the given index.
+    Encryption { key: usize, r: BigUint, c: BigUint },
+    // a <= m <= b, with the bounds as circuit constants as in `IntervalPlaintextCircuit`.
+    Range { a: BigUint, b: BigUint },
+}
+
+// Builds the conjunction of several statements about one plaintext m, e.g., that c1 encrypts
+// m, that m is in some range, and that c2 encrypts m as well. m is allocated once, and so are
+// the public inputs of each distinct key. With two encryptions, this generalizes
+// `SamePlaintextTwoKeysCircuit`.
+#[derive(Clone)]
+pub struct PaillierStatementBuilder<const W: usize> {
+    message_bits: usize,
+    m: BigUint,
+    keys: Vec<(usize, BigUint)>,
+    components: Vec<Component>,
+}
+
+impl<const W: usize> PaillierStatementBuilder<W> {
+    pub fn new(message_bits: usize, m: BigUint) -> Self {
+        Self { message_bits, m, keys: vec![], components: vec![] }
+    }
+
+    // c = Enc(m; r) under the `bits`-bit modulus n. Encryptions under the same n share its
+    // public inputs.
+    pub fn encryption(mut self, bits: usize, n: BigUint, r: BigUint, c: BigUint) -> Self {
+        let key = match self.keys.iter().position(|(_, k)| k == &n) {
+            Some(key) => {
+                assert_eq!(self.keys[key].0, bits);
+                key
+            }
+            None => {
+                self.keys.push((bits, n));
+                self.keys.len() - 1
+            }
+        };
+        self.components.push(Component::Encryption { key, r, c });
+        self
+    }
+
+    pub fn range(mut self, a: BigUint, b: BigUint) -> Self {
+        assert!(a <= b && b.bits() as usize <= self.message_bits);
+        self.components.push(Component::Range { a, b });
+        self
+    }
+
+    pub fn build(self) -> PaillierStatement<W> {
+        PaillierStatement(self)
+    }
+}
+
+// The combined circuit. The public inputs are n^2, g and n of each key in the order the keys
+// first appear, followed by the ciphertexts in the order of the encryptions.
+#[derive(Clone)]
+pub struct PaillierStatement<const W: usize>(PaillierStatementBuilder<W>);
+
+impl<const W: usize> PaillierStatement<W> {
+    pub fn public_inputs<F: PrimeField>(&self) -> Vec<F> {
+        let mut inputs = vec![];
+        for (bits, n) in &self.0.keys {
+            inputs.extend(PublicKeyVar::<F, W>::public_inputs(*bits, n));
+        }
+        for component in &self.0.components {
+            if let Component::Encryption { key, c, .. } = component {
+                inputs.extend(BigUintVar::<F, W>::inputize(c, self.0.keys[*key].0 * 2));
+            }
+        }
+        inputs
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PaillierStatement<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let PaillierStatementBuilder { message_bits, m, keys, components } = self.0;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((m, message_bits)))?;
+
+        let key_vars = keys
+            .into_iter()
+            .map(|(bits, n)| PublicKeyVar::new_input(cs.clone(), bits, n))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for component in components {
+            match component {
+                Component::Encryption { key, r, c } => {
+                    let key = &key_vars[key];
+                    let bits = key.bits;
+                    let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((c, bits * 2)))?;
+                    let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((r, bits)))?;
+                    // As in `SamePlaintextTwoKeysCircuit`, this keeps m below every n that
+                    // encrypts it.
+                    key.enforce_encryption(&c_var, &m_var, &r_var)?;
+                }
+                Component::Range { a, b } => m_var.enforce_in_interval(
+                    &BigUintVar::constant(a, message_bits)?,
+                    &BigUintVar::constant(b, message_bits)?,
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::One;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness},
+        reference::keygen,
+    };
+
+    const W: usize = 32;
+    const N: usize = 128;
+
+    #[test]
+    fn test_encryption_in_range() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let (a, b) = (BigUint::from(10u32), BigUint::from(1_000u32));
+        let statement = |m: &BigUint, c: &BigUint, r: &BigUint| {
+            PaillierStatementBuilder::<W>::new(N, m.clone())
+                .encryption(N, pk.n.clone(), r.clone(), c.clone())
+                .range(a.clone(), b.clone())
+                .build()
+        };
+
+        let m = BigUint::from(500u32);
+        let r = sample_randomness(&pk, rng);
+        let c = encrypt_with_randomness(&pk, &m, &r).0;
+        let params = generate_random_parameters::<Bn254, _, _>(statement(&m, &c, &r), rng)?;
+        let pvk = prepare_verifying_key(&params.vk);
+        let pi = create_random_proof(statement(&m, &c, &r), &params, rng)?;
+        assert!(verify_proof(&pvk, &pi, &statement(&m, &c, &r).public_inputs())?);
+        let c_wrong = &c + BigUint::one();
+        assert!(!verify_proof(&pvk, &pi, &statement(&m, &c_wrong, &r).public_inputs())?);
+
+        // Each component rejects on its own.
+        let out_of_range = BigUint::from(1_001u32);
+        let c_out = encrypt_with_randomness(&pk, &out_of_range, &r).0;
+        for (m, c, satisfied) in [
+            (&m, &c, true),
+            (&m, &c_wrong, false),
+            (&out_of_range, &c_out, false),
+            (&a, &encrypt_with_randomness(&pk, &a, &r).0, true),
+            (&b, &encrypt_with_randomness(&pk, &b, &r).0, true),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            statement(m, c, &r).generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_shared_witnesses() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk1, _, _) = keygen(N, rng);
+        let (pk2, _, _) = keygen(N * 2, rng);
+        let m = BigUint::from(42u32);
+        let mut builder = PaillierStatementBuilder::<W>::new(N, m.clone());
+        let mut encryptions = vec![];
+        for pk in [&pk1, &pk1, &pk2] {
+            let r = sample_randomness(pk, rng);
+            let c = encrypt_with_randomness(pk, &m, &r).0;
+            builder = builder.encryption(pk.bits, pk.n.clone(), r.clone(), c.clone());
+            encryptions.push((r, c));
+        }
+        let statement = builder.build();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        statement.clone().generate_constraints(cs.clone())?;
+        assert!(cs.is_satisfied()?);
+        // pk1 is only allocated once: 5 * N bits for its n^2, g and n, 2 * N for each of its
+        // ciphertexts, then the same for pk2 with twice the size.
+        assert_eq!(cs.num_instance_variables(), 1 + (N * 9 + N * 2 * 7) / W);
+        assert_eq!(statement.public_inputs::<Fr>().len() + 1, cs.num_instance_variables());
+
+        // A different plaintext under the second key breaks the equality.
+        let r = sample_randomness(&pk2, rng);
+        let c = encrypt_with_randomness(&pk2, &(&m + BigUint::one()), &r).0;
+        let (r1, c1) = &encryptions[0];
+        for (r, c, satisfied) in [(&encryptions[2].0, &encryptions[2].1, true), (&r, &c, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierStatementBuilder::<W>::new(N, m.clone())
+                .encryption(N, pk1.n.clone(), r1.clone(), c1.clone())
+                .encryption(N * 2, pk2.n.clone(), r.clone(), c.clone())
+                .build()
+                .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+}
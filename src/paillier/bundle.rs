@@ -0,0 +1,284 @@
+use std::fmt;
+
+use ark_ec::pairing::Pairing;
+use ark_groth16::{prepare_verifying_key, verify_proof, Proof, VerifyingKey};
+use ark_relations::r1cs::SynthesisError;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+use num::BigUint;
+
+use super::groth16;
+
+// Layout (all integers big-endian):
+//   magic        4 bytes   "PAIL"
+//   version      1 byte    VERSION
+//   limb_width   2 bytes
+//   bits         4 bytes   bit length of the modulus n
+//   proof_len    4 bytes
+//   proof        proof_len bytes, compressed `CanonicalSerialize` encoding
+//   n            ceil(bits / 8) bytes
+//   c            ceil(2 * bits / 8) bytes
+pub const MAGIC: &[u8; 4] = b"PAIL";
+pub const VERSION: u8 = 1;
+
+#[derive(Debug)]
+pub enum Error {
+    Truncated,
+    TrailingBytes,
+    BadMagic,
+    UnsupportedVersion(u8),
+    LimbWidthMismatch { expected: usize, found: usize },
+    ValueTooLarge,
+    Serialization(SerializationError),
+    Synthesis(SynthesisError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Truncated => write!(f, "unexpected end of input"),
+            Error::TrailingBytes => write!(f, "unexpected bytes after the end of the bundle"),
+            Error::BadMagic => write!(f, "not a proof bundle"),
+            Error::UnsupportedVersion(v) => write!(f, "unsupported bundle version {}", v),
+            Error::LimbWidthMismatch { expected, found } => {
+                write!(f, "limb width mismatch: expected {}, found {}", expected, found)
+            }
+            Error::ValueTooLarge => write!(f, "public value does not fit in its declared width"),
+            Error::Serialization(e) => write!(f, "{}", e),
+            Error::Synthesis(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<SerializationError> for Error {
+    fn from(e: SerializationError) -> Self {
+        Error::Serialization(e)
+    }
+}
+
+impl From<SynthesisError> for Error {
+    fn from(e: SynthesisError) -> Self {
+        Error::Synthesis(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofBundle<E: Pairing> {
+    pub limb_width: usize,
+    pub bits: usize,
+    pub proof: Proof<E>,
+    pub n: BigUint,
+    pub c: BigUint,
+}
+
+fn put_biguint(out: &mut Vec<u8>, x: &BigUint, len: usize) -> Result<(), Error> {
+    let bytes = x.to_bytes_be();
+    let bytes = if x.bits() == 0 { &[][..] } else { &bytes[..] };
+    if bytes.len() > len {
+        return Err(Error::ValueTooLarge);
+    }
+    out.resize(out.len() + len - bytes.len(), 0);
+    out.extend_from_slice(bytes);
+    Ok(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], Error> {
+    if bytes.len() < len {
+        return Err(Error::Truncated);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn take_u32(bytes: &mut &[u8]) -> Result<usize, Error> {
+    Ok(u32::from_be_bytes(take(bytes, 4)?.try_into().unwrap()) as usize)
+}
+
+impl<E: Pairing> ProofBundle<E> {
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let limb_width = u16::try_from(self.limb_width).map_err(|_| Error::ValueTooLarge)?;
+        let bits = u32::try_from(self.bits).map_err(|_| Error::ValueTooLarge)?;
+        let mut proof = vec![];
+        self.proof.serialize_compressed(&mut proof)?;
+
+        let mut out = vec![];
+        out.extend_from_slice(MAGIC);
+        out.push(VERSION);
+        out.extend_from_slice(&limb_width.to_be_bytes());
+        out.extend_from_slice(&bits.to_be_bytes());
+        out.extend_from_slice(&(proof.len() as u32).to_be_bytes());
+        out.extend_from_slice(&proof);
+        put_biguint(&mut out, &self.n, self.bits.div_ceil(8))?;
+        put_biguint(&mut out, &self.c, (self.bits * 2).div_ceil(8))?;
+        Ok(out)
+    }
+
+    pub fn from_bytes(mut bytes: &[u8]) -> Result<Self, Error> {
+        let bytes = &mut bytes;
+        if take(bytes, MAGIC.len())? != MAGIC {
+            return Err(Error::BadMagic);
+        }
+        let version = take(bytes, 1)?[0];
+        if version != VERSION {
+            return Err(Error::UnsupportedVersion(version));
+        }
+        let limb_width = u16::from_be_bytes(take(bytes, 2)?.try_into().unwrap()) as usize;
+        let bits = take_u32(bytes)?;
+        let proof_len = take_u32(bytes)?;
+        let mut proof_bytes = take(bytes, proof_len)?;
+        let proof = Proof::deserialize_compressed(&mut proof_bytes)?;
+        if !proof_bytes.is_empty() {
+            return Err(Error::TrailingBytes);
+        }
+        let n = BigUint::from_bytes_be(take(bytes, bits.div_ceil(8))?);
+        let c = BigUint::from_bytes_be(take(bytes, (bits * 2).div_ceil(8))?);
+        if !bytes.is_empty() {
+            return Err(Error::TrailingBytes);
+        }
+        if n.bits() as usize > bits || c.bits() as usize > bits * 2 {
+            return Err(Error::ValueTooLarge);
+        }
+        Ok(Self { limb_width, bits, proof, n, c })
+    }
+
+    pub fn public_inputs<const W: usize>(&self) -> Result<Vec<E::ScalarField>, Error> {
+        if self.limb_width != W {
+            return Err(Error::LimbWidthMismatch { expected: W, found: self.limb_width });
+        }
+        Ok(groth16::public_inputs::<E::ScalarField, W>(self.bits, &self.n, &self.c))
+    }
+}
+
+pub fn verify_bytes<E: Pairing, const W: usize>(
+    vk_bytes: &[u8],
+    bundle_bytes: &[u8],
+) -> Result<bool, Error> {
+    let vk = VerifyingKey::<E>::deserialize_compressed(vk_bytes)?;
+    let bundle = ProofBundle::<E>::from_bytes(bundle_bytes)?;
+    let inputs = bundle.public_inputs::<W>()?;
+    Ok(verify_proof(&prepare_verifying_key(&vk), &bundle.proof, &inputs)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bn254::{Bn254, G1Affine, G2Affine};
+    use ark_groth16::{create_random_proof, generate_random_parameters};
+    use num::{bigint::RandBigInt, One};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::PaillierEncCircuit;
+
+    const W: usize = 32;
+    const N: usize = 64;
+
+    const GOLDEN: &str = concat!(
+        "5041494c01002000000010000000800000000000000000000000000000000000",
+        "0000000000000000000000000000400000000000000000000000000000000000",
+        "0000000000000000000000000000000000000000000000000000000000000000",
+        "0000000000000000000000000000400000000000000000000000000000000000",
+        "000000000000000000000000000040c35b12345678",
+    );
+
+    fn from_hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn setup() -> Result<(Vec<u8>, Vec<u8>), Box<dyn std::error::Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let r = rng.gen_biguint_below(&n);
+        let m = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+        let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+
+        let pk = generate_random_parameters::<Bn254, _, _>(
+            PaillierEncCircuit::<W>::new(
+                N,
+                Default::default(),
+                rng.gen_biguint_range(&(BigUint::one() << N), &(BigUint::one() << (N + 1))),
+                Default::default(),
+                Default::default(),
+            ),
+            rng,
+        )?;
+        let proof = create_random_proof(
+            PaillierEncCircuit::<W>::new(N, m, n.clone(), r, c.clone()),
+            &pk,
+            rng,
+        )?;
+
+        let mut vk_bytes = vec![];
+        pk.vk.serialize_compressed(&mut vk_bytes)?;
+        let bundle = ProofBundle::<Bn254> { limb_width: W, bits: N, proof, n, c };
+        Ok((vk_bytes, bundle.to_bytes()?))
+    }
+
+    #[test]
+    fn test_verify_bytes() -> Result<(), Box<dyn std::error::Error>> {
+        let (vk_bytes, bundle_bytes) = setup()?;
+        assert!(verify_bytes::<Bn254, W>(&vk_bytes, &bundle_bytes)?);
+        assert_eq!(ProofBundle::<Bn254>::from_bytes(&bundle_bytes)?.to_bytes()?, bundle_bytes);
+
+        let mut corrupted = bundle_bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert!(!verify_bytes::<Bn254, W>(&vk_bytes, &corrupted)?);
+
+        let mut corrupted = bundle_bytes.clone();
+        corrupted[0] ^= 1;
+        assert!(matches!(verify_bytes::<Bn254, W>(&vk_bytes, &corrupted), Err(Error::BadMagic)));
+
+        let mut corrupted = bundle_bytes.clone();
+        corrupted[4] = VERSION + 1;
+        assert!(matches!(
+            verify_bytes::<Bn254, W>(&vk_bytes, &corrupted),
+            Err(Error::UnsupportedVersion(_))
+        ));
+
+        let mut corrupted = bundle_bytes.clone();
+        corrupted[6] ^= 1;
+        assert!(matches!(
+            verify_bytes::<Bn254, W>(&vk_bytes, &corrupted),
+            Err(Error::LimbWidthMismatch { .. })
+        ));
+
+        let mut corrupted = bundle_bytes.clone();
+        corrupted[16] ^= 1;
+        assert!(!matches!(verify_bytes::<Bn254, W>(&vk_bytes, &corrupted), Ok(true)));
+
+        for len in 0..bundle_bytes.len() {
+            assert!(verify_bytes::<Bn254, W>(&vk_bytes, &bundle_bytes[..len]).is_err());
+        }
+        assert!(verify_bytes::<Bn254, W>(&vk_bytes[..vk_bytes.len() - 1], &bundle_bytes).is_err());
+
+        let mut extended = bundle_bytes.clone();
+        extended.push(0);
+        assert!(matches!(
+            verify_bytes::<Bn254, W>(&vk_bytes, &extended),
+            Err(Error::TrailingBytes)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_golden() -> Result<(), Box<dyn std::error::Error>> {
+        let bundle = ProofBundle::<Bn254> {
+            limb_width: W,
+            bits: 16,
+            proof: Proof { a: G1Affine::default(), b: G2Affine::default(), c: G1Affine::default() },
+            n: BigUint::from(0xc35bu32),
+            c: BigUint::from(0x12345678u32),
+        };
+        let golden = from_hex(GOLDEN);
+        assert_eq!(bundle.to_bytes()?, golden);
+        assert_eq!(ProofBundle::<Bn254>::from_bytes(&golden)?, bundle);
+        Ok(())
+    }
+}
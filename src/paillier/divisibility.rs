@@ -0,0 +1,90 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::{BigUint, Zero};
+
+use super::PublicKeyVar;
+use crate::bn::BigUintVar;
+
+// Proves that c encrypts a multiple m = q * k of k, e.g., an amount in whole units of k cents.
+// Like the interval in `IntervalPlaintextCircuit`, k is a circuit constant.
+#[derive(Clone)]
+pub struct DivisibilityCircuit<const W: usize> {
+    pub bits: usize,
+    pub k: BigUint,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+}
+
+impl<const W: usize> DivisibilityCircuit<W> {
+    pub fn new(bits: usize, k: BigUint, m: BigUint, n: BigUint, r: BigUint, c: BigUint) -> Self {
+        assert!(!k.is_zero() && k.bits() as usize <= bits);
+        Self { bits, k, m, n, r, c }
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for DivisibilityCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        // q < 2^bits / k, so it fits in bits - bits(k) + 1 bits.
+        let q_bits = bits - self.k.bits() as usize + 1;
+        let q = &self.m / &self.k;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, bits)))?;
+        let q_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((q, q_bits)))?;
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits * 2)))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+
+        q_var.mul_by_constant(&self.k)?.enforce_equal_unaligned(&m_var)?;
+        // Dec(c) is m mod n, which is only a multiple of k if m itself is below n, as enforced
+        // here.
+        key.enforce_encryption(&c_var, &m_var, &r_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::One;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{native::sample_randomness, reference::keygen};
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_divisibility() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let k = BigUint::from(100u32);
+
+        for (m, satisfied) in [
+            (BigUint::zero(), true),
+            (BigUint::from(123_400u32), true),
+            (BigUint::from(123_456u32), false),
+            (&pk.n - BigUint::one() - (&pk.n - BigUint::one()) % &k, true),
+            (&pk.n - BigUint::one() - (&pk.n - BigUint::one()) % &k + BigUint::one(), false),
+            // A multiple of k that wraps around n.
+            (&pk.n + &k - &pk.n % &k, false),
+        ] {
+            let r = sample_randomness(&pk, rng);
+            // Not `encrypt_with_randomness`, which rejects m >= n.
+            let c = pk.g().modpow(&m, &pk.nn()) * r.modpow(&pk.n, &pk.nn()) % pk.nn();
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            DivisibilityCircuit::<W>::new(N, k.clone(), m, pk.n.clone(), r, c)
+                .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+}
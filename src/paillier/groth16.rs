@@ -0,0 +1,437 @@
+use ark_ec::pairing::Pairing;
+use ark_ff::PrimeField;
+use ark_groth16::{
+    create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    PreparedVerifyingKey, Proof,
+};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar};
+use ark_relations::r1cs::{
+    ConstraintSynthesizer, ConstraintSystem, ConstraintSystemRef, SynthesisError,
+};
+use num::{BigUint, One, Zero};
+use rand::{CryptoRng, RngCore};
+
+use super::{
+    native::{encrypt_with_randomness, PublicKey},
+    PaillierEncCircuit, PublicKeyVar,
+};
+use crate::bn::{pow2_bound, BigUintVar};
+
+// Everything the shape of the encryption circuit depends on. The limb width is W.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CircuitConfig<const W: usize> {
+    pub bits: usize,
+    pub message_bits: usize,
+}
+
+impl<const W: usize> CircuitConfig<W> {
+    pub fn new(bits: usize) -> Self {
+        Self { bits, message_bits: bits }
+    }
+}
+
+pub struct ProvingKey<E: Pairing, const W: usize> {
+    pub config: CircuitConfig<W>,
+    pub pk: ark_groth16::ProvingKey<E>,
+}
+
+pub struct VerifyingKey<E: Pairing, const W: usize> {
+    pub config: CircuitConfig<W>,
+    pub pvk: PreparedVerifyingKey<E>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EncryptionStatement {
+    pub n: BigUint,
+    pub c: BigUint,
+}
+
+#[derive(Clone, Debug)]
+pub struct EncryptionWitness {
+    pub pk: PublicKey,
+    pub m: BigUint,
+    pub r: BigUint,
+}
+
+impl EncryptionWitness {
+    pub fn statement(&self) -> EncryptionStatement {
+        let c = encrypt_with_randomness(&self.pk, &self.m, &self.r).0;
+        EncryptionStatement { n: self.pk.n.clone(), c }
+    }
+}
+
+// Shape stability: the constraint system of the encryption circuit only depends on the
+// config, never on the values of n, m, r or c, so the keys from one setup serve every modulus
+// of the configured size. No gadget may branch on a value, e.g., to skip work for a zero hint.
+fn placeholder_circuit<const W: usize>(config: CircuitConfig<W>) -> PaillierEncCircuit<W> {
+    // The values are still read during setup, so they must be valid for the widths: m = 0
+    // fits in any message width, n = 2^bits - 1 is odd and of full size, and g is left unset
+    // so that the n + 1 fast path is taken as when proving.
+    PaillierEncCircuit::<W> {
+        message_bits: config.message_bits,
+        ..PaillierEncCircuit::new(
+            config.bits,
+            BigUint::zero(),
+            pow2_bound(config.bits) - BigUint::one(),
+            BigUint::one(),
+            BigUint::zero(),
+        )
+    }
+}
+
+fn shape<F: PrimeField>(
+    circuit: impl ConstraintSynthesizer<F>,
+) -> Result<(usize, usize, usize), SynthesisError> {
+    let cs = ConstraintSystem::<F>::new_ref();
+    circuit.generate_constraints(cs.clone())?;
+    Ok((cs.num_constraints(), cs.num_witness_variables(), cs.num_instance_variables()))
+}
+
+// (number of constraints, of witness variables, of instance variables) for the config.
+pub fn circuit_shape<F: PrimeField, const W: usize>(
+    config: CircuitConfig<W>,
+) -> Result<(usize, usize, usize), SynthesisError> {
+    shape::<F>(placeholder_circuit(config))
+}
+
+pub fn setup<E: Pairing, const W: usize, R: RngCore + CryptoRng>(
+    config: CircuitConfig<W>,
+    rng: &mut R,
+) -> Result<(ProvingKey<E, W>, VerifyingKey<E, W>), SynthesisError> {
+    let pk = generate_random_parameters::<E, _, _>(placeholder_circuit(config), rng)?;
+    let pvk = prepare_verifying_key(&pk.vk);
+    Ok((ProvingKey { config, pk }, VerifyingKey { config, pvk }))
+}
+
+pub fn prove<E: Pairing, const W: usize, R: RngCore + CryptoRng>(
+    pk: &ProvingKey<E, W>,
+    witness: EncryptionWitness,
+    rng: &mut R,
+) -> Result<Proof<E>, SynthesisError> {
+    if witness.pk.bits != pk.config.bits {
+        return Err(SynthesisError::Unsatisfiable);
+    }
+    let circuit = PaillierEncCircuit::<W> {
+        message_bits: pk.config.message_bits,
+        ..PaillierEncCircuit::from_native(&witness.pk, witness.m, witness.r)
+    };
+    debug_assert_eq!(
+        shape::<E::ScalarField>(circuit.clone())?,
+        circuit_shape::<E::ScalarField, W>(pk.config)?
+    );
+    create_random_proof(circuit, &pk.pk, rng)
+}
+
+pub fn verify<E: Pairing, const W: usize>(
+    vk: &VerifyingKey<E, W>,
+    statement: &EncryptionStatement,
+    proof: &Proof<E>,
+) -> Result<bool, SynthesisError> {
+    let bits = vk.config.bits;
+    let EncryptionStatement { n, c } = statement;
+    // `inputize` truncates, so oversized values would be verified as different ones.
+    if n.bits() as usize > bits || c.bits() as usize > bits * 2 {
+        return Ok(false);
+    }
+    verify_proof(&vk.pvk, proof, &public_inputs::<E::ScalarField, W>(bits, n, c))
+}
+
+// The public inputs of the encryption circuit, i.e., the limbs of n^2, g = n + 1, n and c.
+pub fn public_inputs<F: PrimeField, const W: usize>(
+    bits: usize,
+    n: &BigUint,
+    c: &BigUint,
+) -> Vec<F> {
+    [PublicKeyVar::<F, W>::public_inputs(bits, n), BigUintVar::<F, W>::inputize(c, bits * 2)]
+        .concat()
+}
+
+// Same as `public_inputs`, but as variables of an outer circuit over the same field, which is
+// the first step towards verifying a proof of encryption inside another proof. n and c are
+// witnesses, and n^2 and g are allocated with canonical limbs and checked against n, so the
+// limbs are assigned exactly the values of `public_inputs`.
+pub fn paillier_public_input_vars<F: PrimeField, const W: usize>(
+    cs: ConstraintSystemRef<F>,
+    bits: usize,
+    n: &BigUint,
+    c: &BigUint,
+) -> Result<Vec<FpVar<F>>, SynthesisError> {
+    let nn_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((n * n, bits * 2)))?;
+    let g_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((n + BigUint::one(), bits * 2)))?;
+    let n_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((n.clone(), bits)))?;
+    let c_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((c.clone(), bits * 2)))?;
+    n_var.mul_no_carry(&n_var)?.enforce_equal_unaligned(&nn_var)?;
+    n_var
+        .add_no_carry(&BigUintVar::constant(BigUint::one(), 1)?)
+        .enforce_equal_unaligned(&g_var)?;
+    Ok([nn_var, g_var, n_var, c_var]
+        .iter()
+        .flat_map(|x| x.0.iter().map(|limb| limb.0.clone()))
+        .collect())
+}
+
+// A verifying key together with the layout of the public inputs, i.e., the widths of the
+// public values in the order they are allocated, and whether they are packed. This saves
+// callers from encoding the values by hand.
+pub struct PaillierVerifier<E: Pairing, const W: usize> {
+    pub pvk: PreparedVerifyingKey<E>,
+    pub widths: Vec<usize>,
+    pub packed: bool,
+}
+
+impl<E: Pairing, const W: usize> PaillierVerifier<E, W> {
+    pub fn new(vk: &ark_groth16::VerifyingKey<E>, widths: Vec<usize>, packed: bool) -> Self {
+        Self { pvk: prepare_verifying_key(vk), widths, packed }
+    }
+
+    // For `PaillierEncCircuit`, whose public values are n^2, g, n and c.
+    pub fn for_encryption(vk: &ark_groth16::VerifyingKey<E>, bits: usize, packed: bool) -> Self {
+        Self::new(vk, vec![bits * 2, bits * 2, bits, bits * 2], packed)
+    }
+
+    // None if the number of values does not match the layout, or if some value does not fit
+    // in its width.
+    pub fn inputs(&self, values: &[BigUint]) -> Option<Vec<E::ScalarField>> {
+        if values.len() != self.widths.len() {
+            return None;
+        }
+        let mut inputs = vec![];
+        for (x, &l) in values.iter().zip(&self.widths) {
+            if x.bits() as usize > l {
+                return None;
+            }
+            inputs.extend(if self.packed {
+                BigUintVar::<E::ScalarField, W>::inputize_packed(x, l)
+            } else {
+                BigUintVar::<E::ScalarField, W>::inputize(x, l)
+            });
+        }
+        Some(inputs)
+    }
+
+    pub fn verify(&self, proof: &Proof<E>, values: &[BigUint]) -> Result<bool, SynthesisError> {
+        match self.inputs(values) {
+            // A verifying key for the other packing mode expects a different number of inputs.
+            Some(inputs) if inputs.len() + 1 == self.pvk.vk.gamma_abc_g1.len() => {
+                verify_proof(&self.pvk, proof, &inputs)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_r1cs_std::R1CSVar;
+    use ark_relations::r1cs::ConstraintMatrices;
+    use num::bigint::RandBigInt;
+    use num_prime::RandPrime;
+    use rand::{rngs::StdRng, thread_rng, SeedableRng};
+
+    use super::*;
+    use crate::paillier::{native::sample_randomness, reference::keygen};
+
+    const W: usize = 32;
+    const N: usize = 64;
+
+    #[test]
+    fn test_setup_prove_verify() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, p * q);
+
+        for message_bits in [N, 16] {
+            let config = CircuitConfig::<W> { bits: N, message_bits };
+            let (proving_key, verifying_key) = setup::<Bn254, W, _>(config, rng)?;
+
+            let m = rng.gen_biguint_below(&(pow2_bound(message_bits).min(pk.n.clone())));
+            let witness = EncryptionWitness { pk: pk.clone(), m, r: sample_randomness(&pk, rng) };
+            let statement = witness.statement();
+            let proof = prove(&proving_key, witness, rng)?;
+            assert!(verify(&verifying_key, &statement, &proof)?);
+
+            let c = &statement.c + BigUint::one();
+            assert!(!verify(
+                &verifying_key,
+                &EncryptionStatement { c, ..statement.clone() },
+                &proof
+            )?);
+            let c = &statement.c + pow2_bound(N * 2);
+            assert!(!verify(
+                &verifying_key,
+                &EncryptionStatement { c, ..statement.clone() },
+                &proof
+            )?);
+        }
+
+        Ok(())
+    }
+
+    type Matrices = (Vec<Vec<(Fr, usize)>>, Vec<Vec<(Fr, usize)>>, Vec<Vec<(Fr, usize)>>);
+
+    fn matrices(circuit: PaillierEncCircuit<W>) -> Result<Matrices, SynthesisError> {
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.generate_constraints(cs.clone())?;
+        cs.finalize();
+        let ConstraintMatrices { a, b, c, .. } = cs.to_matrices().unwrap();
+        Ok((a, b, c))
+    }
+
+    #[test]
+    fn test_shape_stability() -> Result<(), Box<dyn Error>> {
+        const N: usize = 128;
+        let rng = &mut thread_rng();
+        let config = CircuitConfig::<W>::new(N);
+        let expected = circuit_shape::<Fr, W>(config)?;
+        let expected_matrices = matrices(placeholder_circuit(config))?;
+
+        let mut circuits = vec![];
+        for _ in 0..17 {
+            let pk = keygen(N, rng).0;
+            let m = rng.gen_biguint_below(&pk.n);
+            circuits.push(PaillierEncCircuit::<W>::from_native(
+                &pk,
+                m,
+                sample_randomness(&pk, rng),
+            ));
+        }
+        // n just above 2^(N - 1) with m = 0, n = 2^N - 1 with r = 1, and both at once. The
+        // moduli are not products of two primes, which the circuit does not check anyway.
+        let low = PublicKey::new(N, pow2_bound(N - 1) + BigUint::one());
+        let high = PublicKey::new(N, pow2_bound(N) - BigUint::one());
+        circuits.push(PaillierEncCircuit::from_native(&low, BigUint::zero(), BigUint::from(2u32)));
+        circuits.push(PaillierEncCircuit::from_native(&high, BigUint::from(5u32), BigUint::one()));
+        circuits.push(PaillierEncCircuit::from_native(&low, BigUint::zero(), BigUint::one()));
+
+        for circuit in circuits {
+            assert_eq!(shape::<Fr>(circuit.clone())?, expected);
+            assert!(matrices(circuit)? == expected_matrices);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paillier_verifier() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&pk.n);
+        let circuit = PaillierEncCircuit::<W>::from_native(&pk, m, sample_randomness(&pk, rng));
+        let values = [pk.nn(), pk.g(), pk.n.clone(), circuit.c.clone()];
+
+        let mut verifiers = vec![];
+        let mut proofs = vec![];
+        for pack_inputs in [false, true] {
+            let params = generate_random_parameters::<Bn254, _, _>(
+                PaillierEncCircuit { pack_inputs, ..placeholder_circuit(CircuitConfig::new(N)) },
+                rng,
+            )?;
+            let pi = create_random_proof(
+                PaillierEncCircuit { pack_inputs, ..circuit.clone() },
+                &params,
+                rng,
+            )?;
+            let verifier = PaillierVerifier::<Bn254, W>::for_encryption(&params.vk, N, pack_inputs);
+            assert!(verifier.verify(&pi, &values)?);
+            let mut c = values.clone();
+            c[3] += BigUint::one();
+            assert!(!verifier.verify(&pi, &c)?);
+            assert!(!verifier.verify(&pi, &values[..3])?);
+            verifiers.push(verifier);
+            proofs.push(pi);
+        }
+        assert!(
+            verifiers[1].inputs(&values).unwrap().len()
+                < verifiers[0].inputs(&values).unwrap().len()
+        );
+
+        // Proofs only verify in the mode of their key.
+        let mismatched = PaillierVerifier::<Bn254, W> { packed: true, ..verifiers.remove(0) };
+        assert!(!mismatched.verify(&proofs[0], &values)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_minimal_public_inputs() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&pk.n);
+        let circuit = PaillierEncCircuit::<W> {
+            minimal_inputs: true,
+            ..PaillierEncCircuit::from_native(&pk, m, sample_randomness(&pk, rng))
+        };
+        let params = generate_random_parameters::<Bn254, _, _>(
+            PaillierEncCircuit {
+                minimal_inputs: true,
+                ..placeholder_circuit(CircuitConfig::new(N))
+            },
+            rng,
+        )?;
+        let pvk = prepare_verifying_key(&params.vk);
+        let c = circuit.c.clone();
+        let pi = create_random_proof(circuit, &params, rng)?;
+
+        let inputs = PaillierEncCircuit::<W>::minimal_public_inputs::<Fr>(N, &pk.n, &c);
+        assert_eq!(inputs.len(), (N * 3).div_ceil(W));
+        assert!(verify_proof(&pvk, &pi, &inputs)?);
+        let c = c + BigUint::one();
+        let inputs = PaillierEncCircuit::<W>::minimal_public_inputs::<Fr>(N, &pk.n, &c);
+        assert!(!verify_proof(&pvk, &pi, &inputs)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_paillier_public_input_vars() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&pk.n);
+        let c = encrypt_with_randomness(&pk, &m, &sample_randomness(&pk, rng)).0;
+        // n = 2^N - 1 has the largest n^2 and g of the width.
+        for (n, c) in [(pk.n.clone(), c), (pow2_bound(N) - BigUint::one(), BigUint::zero())] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let vars = paillier_public_input_vars::<Fr, W>(cs.clone(), N, &n, &c)?;
+            assert_eq!(vars.value()?, public_inputs::<Fr, W>(N, &n, &c));
+            assert!(cs.is_satisfied()?);
+        }
+        Ok(())
+    }
+
+    // Everything is derived from the seed, including the setup, so the proof is reproducible.
+    fn seeded_proof(
+        bits: usize,
+        seed: u64,
+    ) -> Result<(VerifyingKey<Bn254, W>, EncryptionStatement, Proof<Bn254>), SynthesisError> {
+        let rng = &mut StdRng::seed_from_u64(seed);
+        let (pk, _, _) = keygen(bits, rng);
+        let (proving_key, verifying_key) = setup::<Bn254, W, _>(CircuitConfig::new(bits), rng)?;
+        let m = rng.gen_biguint_below(&pk.n);
+        let witness = EncryptionWitness { r: sample_randomness(&pk, rng), pk, m };
+        let statement = witness.statement();
+        Ok((verifying_key, statement, prove(&proving_key, witness, rng)?))
+    }
+
+    // Also runs on wasm32-unknown-unknown with `wasm-pack test --node --features
+    // expensive-tests`, which has no OS randomness and no threads.
+    #[cfg(feature = "expensive-tests")]
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+    fn test_seeded_1024() {
+        let (verifying_key, statement, proof) = seeded_proof(1024, 0).unwrap();
+        assert!(verify(&verifying_key, &statement, &proof).unwrap());
+    }
+
+    #[test]
+    fn test_seeded_reproducible() -> Result<(), Box<dyn Error>> {
+        let (_, statement, proof) = seeded_proof(N, 1)?;
+        assert_eq!(seeded_proof(N, 1)?.1, statement);
+        assert_eq!(seeded_proof(N, 1)?.2, proof);
+        assert_ne!(seeded_proof(N, 2)?.2, proof);
+        Ok(())
+    }
+}
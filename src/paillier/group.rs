@@ -0,0 +1,158 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, R1CSVar};
+use ark_relations::r1cs::SynthesisError;
+use num::{BigInt, BigUint, Integer, One, Zero};
+
+use crate::bn::BigUintVar;
+
+// The operations of a multiplicative group of integers modulo some m, e.g., Z*_{n^2} for
+// Paillier, Z*_{n^(s+1)} for Damgard-Jurik or Z*_n for RSA, so that protocol gadgets can be
+// written once for all of them. All results are fully reduced.
+pub trait ModularGroup<F: PrimeField, const W: usize> {
+    fn identity(&self) -> Result<BigUintVar<F, W>, SynthesisError>;
+
+    fn mul(
+        &self,
+        x: &BigUintVar<F, W>,
+        y: &BigUintVar<F, W>,
+    ) -> Result<BigUintVar<F, W>, SynthesisError>;
+
+    fn pow(
+        &self,
+        x: &BigUintVar<F, W>,
+        e: &[Boolean<F>],
+    ) -> Result<BigUintVar<F, W>, SynthesisError>;
+
+    // Also enforces that x is invertible.
+    fn inverse(&self, x: &BigUintVar<F, W>) -> Result<BigUintVar<F, W>, SynthesisError>;
+}
+
+// Z*_{n^2}, where the ciphertexts live. `nn_lbound` is a lower bound on n^2, as in
+// `BigUintVar::rem`.
+#[derive(Clone)]
+pub struct PaillierGroup<F: PrimeField, const W: usize> {
+    pub nn: BigUintVar<F, W>,
+    pub nn_lbound: BigUint,
+}
+
+impl<F: PrimeField, const W: usize> PaillierGroup<F, W> {
+    pub fn new(nn: BigUintVar<F, W>, nn_lbound: BigUint) -> Self {
+        Self { nn, nn_lbound }
+    }
+}
+
+impl<F: PrimeField, const W: usize> ModularGroup<F, W> for PaillierGroup<F, W> {
+    fn identity(&self) -> Result<BigUintVar<F, W>, SynthesisError> {
+        BigUintVar::constant(BigUint::one(), 1)
+    }
+
+    fn mul(
+        &self,
+        x: &BigUintVar<F, W>,
+        y: &BigUintVar<F, W>,
+    ) -> Result<BigUintVar<F, W>, SynthesisError> {
+        x.mul_no_carry(y)?.rem_reduced(&self.nn, &self.nn_lbound)
+    }
+
+    fn pow(
+        &self,
+        x: &BigUintVar<F, W>,
+        e: &[Boolean<F>],
+    ) -> Result<BigUintVar<F, W>, SynthesisError> {
+        x.clone().powm(e, &self.nn, &self.nn_lbound)
+    }
+
+    fn inverse(&self, x: &BigUintVar<F, W>) -> Result<BigUintVar<F, W>, SynthesisError> {
+        let cs = x.cs().or(self.nn.cs());
+        let bits = self.nn.ubound().bits() as usize;
+        let y = {
+            let x = BigInt::from(x.value().unwrap_or_default());
+            let nn = BigInt::from(self.nn.value().unwrap_or(BigUint::one()));
+            let gcd = x.extended_gcd(&nn);
+            // A non-invertible x gets 0, which leaves the system unsatisfied.
+            let y = if gcd.gcd.is_one() { gcd.x.mod_floor(&nn) } else { BigInt::zero() };
+            let y = y.to_biguint().unwrap();
+            if cs.is_none() {
+                BigUintVar::constant(y, bits)?
+            } else {
+                BigUintVar::new_witness(cs, || Ok((y, bits)))?
+            }
+        };
+        // x * y = 1 (mod n^2) fixes y modulo n^2, and y < n^2 makes it unique. The remainder
+        // is compared to 1 directly, so it needs no check against n^2.
+        y.enforce_lt(&self.nn)?;
+        x.mul_no_carry(&y)?
+            .rem(&self.nn, &self.nn_lbound)?
+            .enforce_equal_unaligned(&self.identity()?)?;
+        Ok(y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_r1cs_std::ToBitsGadget;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::bigint::RandBigInt;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::{
+        bn::pow2_bound,
+        paillier::{
+            native::{encrypt_with_randomness, sample_randomness},
+            reference::{decrypt, keygen},
+        },
+    };
+
+    const W: usize = 32;
+    const N: usize = 128;
+
+    #[test]
+    fn test_paillier_group() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, p, q) = keygen(N, rng);
+        let nn = pk.nn();
+        let (m1, m2) = (rng.gen_biguint_below(&pk.n), rng.gen_biguint_below(&pk.n));
+        let c1 = encrypt_with_randomness(&pk, &m1, &sample_randomness(&pk, rng)).0;
+        let c2 = encrypt_with_randomness(&pk, &m2, &sample_randomness(&pk, rng)).0;
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+        let c1_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((c1.clone(), N * 2)))?;
+        let c2_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((c2.clone(), N * 2)))?;
+        let group = PaillierGroup::new(nn_var.clone(), pow2_bound(N * 2));
+
+        // The trait-based homomorphic addition is the direct one.
+        let before = cs.num_constraints();
+        let sum = group.mul(&c1_var, &c2_var)?;
+        let trait_cost = cs.num_constraints() - before;
+        let before = cs.num_constraints();
+        let direct = c1_var.mul_no_carry(&c2_var)?.rem(&nn_var, &pow2_bound(N * 2))?;
+        direct.enforce_lt(&nn_var)?;
+        assert_eq!(cs.num_constraints() - before, trait_cost);
+        assert_eq!(sum.value()?, direct.value()?);
+        assert_eq!(decrypt(&p, &q, &sum.value()?), (&m1 + &m2) % &pk.n);
+
+        let inv = group.inverse(&c2_var)?;
+        assert_eq!(group.mul(&inv, &c2_var)?.value()?, BigUint::one());
+        let diff = group.mul(&c1_var, &inv)?.value()?;
+        assert_eq!(decrypt(&p, &q, &diff), (&m1 + &pk.n - &m2) % &pk.n);
+
+        let e = BigUintVar::<Fr, W>::constant(BigUint::from(3u32), 2)?.to_bits_le()?;
+        assert_eq!(group.pow(&c1_var, &e)?.value()?, c1.modpow(&BigUint::from(3u32), &nn));
+        assert_eq!(group.identity()?.value()?, BigUint::one());
+        assert!(cs.is_satisfied()?);
+
+        // A multiple of p has no inverse.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+        let x = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((&p * &q * &p, N * 2)))?;
+        PaillierGroup::new(nn_var, pow2_bound(N * 2)).inverse(&x)?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{
+        constraints::PoseidonSpongeVar, find_poseidon_ark_and_mds, PoseidonConfig, PoseidonSponge,
+    },
+    CryptographicSponge,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::EqGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::PublicKeyVar;
+use crate::bn::BigUintVar;
+
+pub fn poseidon_config<F: PrimeField>() -> PoseidonConfig<F> {
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(F::MODULUS_BIT_SIZE as u64, 2, 8, 57, 0);
+    PoseidonConfig::new(8, 57, 5, mds, ark, 2, 1)
+}
+
+// Poseidon hash of the W-bit limbs of a `bits`-bit plaintext.
+pub fn hash_plaintext<F: PrimeField, const W: usize>(m: &BigUint, bits: usize) -> F {
+    let mut sponge = PoseidonSponge::new(&poseidon_config());
+    sponge.absorb(&BigUintVar::<F, W>::inputize(m, bits));
+    sponge.squeeze_field_elements(1)[0]
+}
+
+// Proves that c encrypts the plaintext hashed to the public `digest`.
+#[derive(Clone)]
+pub struct CiphertextHashLinkCircuit<F: PrimeField, const W: usize> {
+    pub bits: usize,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+    pub digest: F,
+}
+
+impl<F: PrimeField, const W: usize> CiphertextHashLinkCircuit<F, W> {
+    pub fn new(bits: usize, m: BigUint, n: BigUint, r: BigUint, c: BigUint, digest: F) -> Self {
+        Self { bits, m, n, r, c, digest }
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for CiphertextHashLinkCircuit<F, W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, bits)))?;
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits * 2)))?;
+        let digest_var = FpVar::new_input(cs.clone(), || Ok(self.digest))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+
+        key.enforce_encryption(&c_var, &m_var, &r_var)?;
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &poseidon_config());
+        sponge.absorb(&m_var.0.iter().map(|limb| limb.0.clone()).collect::<Vec<_>>())?;
+        sponge.squeeze_field_elements(1)?[0].enforce_equal(&digest_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::native::{encrypt_with_randomness, sample_randomness, PublicKey};
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_hash_link() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, p * q);
+        let m = rng.gen_biguint_below(&pk.n);
+        let r = sample_randomness(&pk, rng);
+        let c = encrypt_with_randomness(&pk, &m, &r).0;
+        let other = (&m + BigUint::one()) % &pk.n;
+
+        for (digest, satisfied) in [
+            (hash_plaintext::<Fr, W>(&m, N), true),
+            (hash_plaintext::<Fr, W>(&other, N), false),
+            (hash_plaintext::<Fr, W>(&m, N * 2), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            CiphertextHashLinkCircuit::<Fr, W>::new(
+                N,
+                m.clone(),
+                pk.n.clone(),
+                r.clone(),
+                c.clone(),
+                digest,
+            )
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // The hash alone does not make a wrong plaintext acceptable.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        CiphertextHashLinkCircuit::<Fr, W>::new(
+            N,
+            other.clone(),
+            pk.n.clone(),
+            r.clone(),
+            c,
+            hash_plaintext::<Fr, W>(&other, N),
+        )
+        .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        // Nor does m + n, which encrypts to the same c but hashes differently.
+        let m = BigUint::from(7u32);
+        let c = encrypt_with_randomness(&pk, &m, &r).0;
+        let other = &m + &pk.n;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        CiphertextHashLinkCircuit::<Fr, W>::new(
+            N,
+            other.clone(),
+            pk.n.clone(),
+            r,
+            c,
+            hash_plaintext::<Fr, W>(&other, N),
+        )
+        .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
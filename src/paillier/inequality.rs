@@ -0,0 +1,113 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::{BigUint, Zero};
+
+use super::{groth16, PublicKeyVar};
+use crate::bn::BigUintVar;
+
+// Proves that c1 and c2 under the same n encrypt different plaintexts, by exhibiting the
+// difference d = m1 - m2 mod n and showing d != 0.
+#[derive(Clone)]
+pub struct InequalityCircuit<const W: usize> {
+    pub bits: usize,
+    pub n: BigUint,
+    pub m1: BigUint,
+    pub r1: BigUint,
+    pub c1: BigUint,
+    pub m2: BigUint,
+    pub r2: BigUint,
+    pub c2: BigUint,
+}
+
+impl<const W: usize> InequalityCircuit<W> {
+    pub fn public_inputs<F: PrimeField>(
+        bits: usize,
+        n: &BigUint,
+        c1: &BigUint,
+        c2: &BigUint,
+    ) -> Vec<F> {
+        [groth16::public_inputs::<F, W>(bits, n, c1), BigUintVar::<F, W>::inputize(c2, bits * 2)]
+            .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for InequalityCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        // m2 + d = m1 + k * n, where k is set iff the subtraction wraps around.
+        let k = self.m1 < self.m2;
+        let d = if k { &self.m1 + &self.n - &self.m2 } else { &self.m1 - &self.m2 };
+
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let mut m_vars = vec![];
+        for (m, r, c) in [(self.m1, self.r1, self.c1), (self.m2, self.r2, self.c2)] {
+            let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((c, bits * 2)))?;
+            let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((m, bits)))?;
+            let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((r, bits)))?;
+            key.enforce_encryption(&c_var, &m_var, &r_var)?;
+            m_vars.push(m_var);
+        }
+
+        let d_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((d, bits)))?;
+        let k_var =
+            BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((BigUint::from(k as u8), 1)))?;
+        d_var.enforce_lt(&key.n)?;
+        m_vars[1]
+            .add_no_carry(&d_var)
+            .enforce_equal_unaligned(&m_vars[0].add_no_carry(&key.n.mul_no_carry(&k_var)?))?;
+        d_var.enforce_neq(&BigUintVar::constant(BigUint::zero(), bits)?)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness},
+        reference::keygen,
+    };
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_inequality() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&(&pk.n - BigUint::one()));
+
+        for (m1, m2, satisfied) in [
+            (m.clone(), &m + BigUint::one(), true),
+            (&m + BigUint::one(), m.clone(), true),
+            (BigUint::zero(), &pk.n - BigUint::one(), true),
+            (m.clone(), m.clone(), false),
+        ] {
+            let (r1, r2) = (sample_randomness(&pk, rng), sample_randomness(&pk, rng));
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            InequalityCircuit::<W> {
+                bits: N,
+                n: pk.n.clone(),
+                c1: encrypt_with_randomness(&pk, &m1, &r1).0,
+                c2: encrypt_with_randomness(&pk, &m2, &r2).0,
+                m1,
+                r1,
+                m2,
+                r2,
+            }
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::PublicKeyVar;
+use crate::bn::BigUintVar;
+
+// Proves that c encrypts some m with a <= m <= b. The interval is baked into the circuit as
+// constants, so it is fixed by the verifying key rather than passed as a public input.
+#[derive(Clone)]
+pub struct IntervalPlaintextCircuit<const W: usize> {
+    pub bits: usize,
+    pub a: BigUint,
+    pub b: BigUint,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+}
+
+impl<const W: usize> IntervalPlaintextCircuit<W> {
+    pub fn new(
+        bits: usize,
+        a: BigUint,
+        b: BigUint,
+        m: BigUint,
+        n: BigUint,
+        r: BigUint,
+        c: BigUint,
+    ) -> Self {
+        assert!(a <= b && b.bits() as usize <= bits);
+        Self { bits, a, b, m, n, r, c }
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for IntervalPlaintextCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, bits)))?;
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits * 2)))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+
+        m_var.enforce_in_interval(
+            &BigUintVar::constant(self.a, bits)?,
+            &BigUintVar::constant(self.b, bits)?,
+        )?;
+
+        // The interval may reach beyond n, so this relies on the m < n of the encryption.
+        key.enforce_encryption(&c_var, &m_var, &r_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::One;
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::native::{encrypt_with_randomness, sample_randomness, PublicKey};
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_interval() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, p * q);
+        let a = BigUint::one();
+        let b = BigUint::from(1_000_000u32);
+
+        for (m, satisfied) in [
+            (BigUint::from(0u32), false),
+            (a.clone(), true),
+            (BigUint::from(123_456u32), true),
+            (b.clone(), true),
+            (&b + BigUint::one(), false),
+            (&pk.n - BigUint::one(), false),
+        ] {
+            let r = sample_randomness(&pk, rng);
+            let c = encrypt_with_randomness(&pk, &m, &r).0;
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            IntervalPlaintextCircuit::<W>::new(N, a.clone(), b.clone(), m, pk.n.clone(), r, c)
+                .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // Dec(c) + n encrypts to the same c and lies in an interval up to 2^N - 1.
+        let m = BigUint::from(5u32);
+        let r = sample_randomness(&pk, rng);
+        let c = encrypt_with_randomness(&pk, &m, &r).0;
+        let b = (BigUint::one() << N) - BigUint::one();
+        for (m, satisfied) in [(m.clone(), true), (&m + &pk.n, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            IntervalPlaintextCircuit::<W>::new(
+                N,
+                a.clone(),
+                b.clone(),
+                m,
+                pk.n.clone(),
+                r.clone(),
+                c.clone(),
+            )
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+}
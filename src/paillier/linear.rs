@@ -0,0 +1,218 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, ToBitsGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::{enforce_valid_ciphertext, PublicKeyVar};
+use crate::bn::{pow2_bound, BigUintVar};
+
+// Proves out = prod_i c_i^(a_i) * g^b * r^n mod n^2, i.e., out encrypts sum_i a_i * m_i + b for
+// public weights a_i of at most `weight_bits` bits. The weights are circuit constants unless
+// `public_weights` is set, in which case they are public inputs of `weight_bits` bits each.
+// With `validate_ciphertexts`, every c_i is also checked to be well-formed.
+#[derive(Clone)]
+pub struct LinearCombinationCircuit<const W: usize> {
+    pub bits: usize,
+    pub weight_bits: usize,
+    pub public_weights: bool,
+    pub weights: Vec<BigUint>,
+    pub n: BigUint,
+    pub ciphertexts: Vec<BigUint>,
+    pub b: BigUint,
+    pub r: BigUint,
+    pub out: BigUint,
+    pub validate_ciphertexts: bool,
+}
+
+impl<const W: usize> LinearCombinationCircuit<W> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        bits: usize,
+        weight_bits: usize,
+        weights: Vec<BigUint>,
+        n: BigUint,
+        ciphertexts: Vec<BigUint>,
+        b: BigUint,
+        r: BigUint,
+        out: BigUint,
+    ) -> Self {
+        assert_eq!(weights.len(), ciphertexts.len());
+        assert!(weights.iter().all(|a| a.bits() as usize <= weight_bits));
+        Self {
+            bits,
+            weight_bits,
+            public_weights: false,
+            weights,
+            n,
+            ciphertexts,
+            b,
+            r,
+            out,
+            validate_ciphertexts: false,
+        }
+    }
+
+    pub fn public_inputs<F: PrimeField>(&self) -> Vec<F> {
+        let bits = self.bits;
+        let n = &self.n;
+        let mut inputs = vec![PublicKeyVar::<F, W>::public_inputs(bits, n)];
+        if self.public_weights {
+            inputs.extend(
+                self.weights.iter().map(|a| BigUintVar::<F, W>::inputize(a, self.weight_bits)),
+            );
+        }
+        inputs.extend(self.ciphertexts.iter().map(|c| BigUintVar::<F, W>::inputize(c, bits * 2)));
+        inputs.push(BigUintVar::<F, W>::inputize(&self.out, bits * 2));
+        inputs.concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for LinearCombinationCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let weight_bits = self.weight_bits;
+        let PublicKeyVar { nn: nn_var, g: g_var, n: n_var, .. } =
+            PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let weight_vars = self
+            .weights
+            .into_iter()
+            .map(|a| {
+                if self.public_weights {
+                    BigUintVar::<F, W>::new_input(cs.clone(), || Ok((a, weight_bits)))
+                } else {
+                    BigUintVar::<F, W>::constant(a, weight_bits)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let c_vars = self
+            .ciphertexts
+            .into_iter()
+            .map(|c| BigUintVar::<F, W>::new_input(cs.clone(), || Ok((c, bits * 2))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let out_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.out, bits * 2)))?;
+        let b_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.b, bits)))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+
+        b_var.enforce_lt(&n_var)?;
+        r_var.enforce_lt(&n_var)?;
+        if self.validate_ciphertexts {
+            for c_var in &c_vars {
+                enforce_valid_ciphertext(c_var, &n_var, &nn_var)?;
+            }
+        }
+
+        // All terms share the squarings of a single multi-exponentiation. The exponents are
+        // padded to the longest one, but the windows of a short constant weight are constant
+        // zeros above its actual length and are skipped.
+        let mut exps = weight_vars
+            .iter()
+            .map(|a| a.to_bits_le_truncated(weight_bits))
+            .collect::<Result<Vec<_>, _>>()?;
+        exps.push(b_var.to_bits_le()?);
+        exps.push(n_var.to_bits_le()?);
+        BigUintVar::multi_powm(
+            &[c_vars, vec![g_var, r_var]].concat(),
+            &exps,
+            &nn_var,
+            &pow2_bound(bits * 2),
+        )?
+        .enforce_equal_unaligned(&out_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness, PublicKey},
+        reference::decrypt,
+    };
+
+    const W: usize = 32;
+    const N: usize = 128;
+
+    #[test]
+    fn test_linear_combination() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, &p * &q);
+        let nn = pk.nn();
+
+        let ms = (0..3).map(|_| rng.gen_biguint_below(&pk.n)).collect::<Vec<_>>();
+        let ciphertexts = ms
+            .iter()
+            .map(|m| encrypt_with_randomness(&pk, m, &sample_randomness(&pk, rng)).0)
+            .collect::<Vec<_>>();
+        let weights = vec![BigUint::from(3u32), BigUint::from(65535u32), BigUint::one()];
+        let b = rng.gen_biguint_below(&pk.n);
+        let r = sample_randomness(&pk, rng);
+        let out = ciphertexts
+            .iter()
+            .zip(&weights)
+            .fold(encrypt_with_randomness(&pk, &b, &r).0, |acc, (c, a)| {
+                acc * c.modpow(a, &nn) % &nn
+            });
+        assert_eq!(
+            decrypt(&p, &q, &out),
+            (ms.iter().zip(&weights).map(|(m, a)| m * a).sum::<BigUint>() + &b) % &pk.n
+        );
+
+        let circuit = LinearCombinationCircuit::<W>::new(
+            N,
+            16,
+            weights.clone(),
+            pk.n.clone(),
+            ciphertexts,
+            b,
+            r,
+            out.clone(),
+        );
+        for (public_weights, validate_ciphertexts) in [(false, false), (true, true)] {
+            let circuit = LinearCombinationCircuit {
+                public_weights,
+                validate_ciphertexts,
+                ..circuit.clone()
+            };
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            LinearCombinationCircuit { out: &out + BigUint::one(), ..circuit.clone() }
+                .generate_constraints(cs.clone())?;
+            assert!(!cs.is_satisfied()?);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let mut weights = weights.clone();
+            weights[0] += BigUint::one();
+            LinearCombinationCircuit { weights, ..circuit.clone() }
+                .generate_constraints(cs.clone())?;
+            assert!(!cs.is_satisfied()?);
+        }
+
+        let circuit = LinearCombinationCircuit { public_weights: true, ..circuit };
+        let params = generate_random_parameters::<Bn254, _, _>(circuit.clone(), rng)?;
+        let pi = create_random_proof(circuit.clone(), &params, rng)?;
+        assert!(verify_proof(
+            &prepare_verifying_key(&params.vk),
+            &pi,
+            &circuit.public_inputs::<Fr>()
+        )?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,745 @@
+use std::marker::PhantomData;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::{alloc::AllocVar, boolean::Boolean, R1CSVar, ToBitsGadget};
+use ark_relations::{
+    ns,
+    r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError},
+};
+use num::{BigUint, One};
+
+use crate::bn::{ops::BigUintOps, pow2_bound, BigUintVar, Reducer, ReductionStrategy};
+
+use self::native::{encrypt_with_randomness, PublicKey};
+
+pub mod accumulate;
+pub mod batch;
+pub mod builder;
+pub mod bundle;
+pub mod divisibility;
+pub mod groth16;
+pub mod group;
+pub mod hash;
+pub mod inequality;
+pub mod interval;
+pub mod linear;
+pub mod native;
+pub mod order;
+pub mod padded;
+pub mod prf;
+pub mod reference;
+pub mod same_plaintext;
+pub mod shuffle;
+pub mod sub;
+pub mod threshold;
+
+// Paillier encryption c = g^m * r^n mod n^2 with g = n + 1, over any bignum backend. Neither
+// function checks c against anything, and `nn_lbound` is a lower bound on n^2.
+pub struct PaillierEncGadget<F: PrimeField, B: BigUintOps<F>>(PhantomData<(F, B)>);
+
+impl<F: PrimeField, B: BigUintOps<F>> PaillierEncGadget<F, B> {
+    pub fn encrypt(
+        g: &B,
+        n: &B,
+        nn: &B,
+        m: &B,
+        r: &B,
+        nn_lbound: &BigUint,
+    ) -> Result<B, SynthesisError> {
+        let _ns = ns!(g.cs().or(r.cs()), "encrypt");
+        B::multi_powm(&[g.clone(), r.clone()], &[m.to_bits_le()?, n.to_bits_le()?], nn, nn_lbound)
+    }
+
+    pub fn encrypt_with_gm(
+        n: &B,
+        nn: &B,
+        gm: &B,
+        r: &B,
+        nn_lbound: &BigUint,
+    ) -> Result<B, SynthesisError> {
+        let cs = r.cs().or(gm.cs());
+        let _ns = ns!(cs, "encrypt");
+        let rn = {
+            let _ns = ns!(cs, "r_pow_n");
+            r.powm(&n.to_bits_le()?, nn, nn_lbound)?
+        };
+        let c = rn.mul_mod(gm, nn, nn_lbound)?;
+        c.enforce_lt(nn)?;
+        Ok(c)
+    }
+}
+
+// `encrypt` and `encrypt_with_gm` for `BigUintVar`, reducing modulo n^2 with `reducer`.
+impl<F: PrimeField, const W: usize> PaillierEncGadget<F, BigUintVar<F, W>> {
+    pub fn encrypt_with_strategy(
+        g: &BigUintVar<F, W>,
+        n: &BigUintVar<F, W>,
+        m: &BigUintVar<F, W>,
+        r: &BigUintVar<F, W>,
+        reducer: &Reducer<F, W>,
+    ) -> Result<BigUintVar<F, W>, SynthesisError> {
+        let _ns = ns!(g.cs().or(r.cs()), "encrypt");
+        BigUintVar::multi_powm_with(
+            &[g.clone(), r.clone()],
+            &[m.to_bits_le()?, n.to_bits_le()?],
+            reducer,
+        )
+    }
+
+    pub fn encrypt_with_gm_strategy(
+        n: &BigUintVar<F, W>,
+        gm: &BigUintVar<F, W>,
+        r: &BigUintVar<F, W>,
+        reducer: &Reducer<F, W>,
+    ) -> Result<BigUintVar<F, W>, SynthesisError> {
+        let cs = r.cs().or(gm.cs());
+        let _ns = ns!(cs, "encrypt");
+        let rn = {
+            let _ns = ns!(cs, "r_pow_n");
+            r.clone().powm_with(&n.to_bits_le()?, reducer)?
+        };
+        let c = rn.mul_mod_with(gm, reducer)?;
+        c.enforce_lt(reducer.modulus())?;
+        Ok(c)
+    }
+}
+
+// The bits of the decryption exponent lambda = lcm(p - 1, q - 1) for the witnessed factors
+// of the `bits`-bit n = p * q. lambda < n, so the bits are truncated to the width of n, which
+// keeps the exponentiation loop as long as for any other exponent below n.
+pub fn carmichael_lambda<F: PrimeField, const W: usize>(
+    p: &BigUintVar<F, W>,
+    q: &BigUintVar<F, W>,
+    bits: usize,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    let _ns = ns!(p.cs().or(q.cs()), "carmichael_lambda");
+    p.sub_one()?.lcm(&q.sub_one()?)?.to_bits_le_truncated(bits)
+}
+
+// Enforces that c is a well-formed ciphertext, i.e., c < n^2 and gcd(c, n) = 1. Otherwise, a
+// common factor of c and n would reveal the factorization of n.
+pub fn enforce_valid_ciphertext<F: PrimeField, const W: usize>(
+    c: &BigUintVar<F, W>,
+    n: &BigUintVar<F, W>,
+    nn: &BigUintVar<F, W>,
+) -> Result<(), SynthesisError> {
+    c.enforce_lt(nn)?;
+    c.enforce_coprime(n)
+}
+
+// The public key of an encryption statement, allocated as the public inputs n^2, g = n + 1
+// and n, in this order. Circuits allocate their ciphertexts after it, so that a single
+// encryption has the layout of `groth16::public_inputs`.
+pub struct PublicKeyVar<F: PrimeField, const W: usize> {
+    pub bits: usize,
+    pub nn: BigUintVar<F, W>,
+    pub g: BigUintVar<F, W>,
+    pub n: BigUintVar<F, W>,
+}
+
+impl<F: PrimeField, const W: usize> PublicKeyVar<F, W> {
+    pub fn new_input(
+        cs: ConstraintSystemRef<F>,
+        bits: usize,
+        n: BigUint,
+    ) -> Result<Self, SynthesisError> {
+        let nn = BigUintVar::new_input(cs.clone(), || Ok((&n * &n, bits * 2)))?;
+        let g = BigUintVar::new_input(cs.clone(), || Ok((&n + BigUint::one(), bits * 2)))?;
+        let n = BigUintVar::new_input(cs, || Ok((n, bits)))?;
+        Ok(Self { bits, nn, g, n })
+    }
+
+    pub fn public_inputs(bits: usize, n: &BigUint) -> Vec<F> {
+        [
+            BigUintVar::<F, W>::inputize(&(n * n), bits * 2),
+            BigUintVar::<F, W>::inputize(&(n + BigUint::one()), bits * 2),
+            BigUintVar::<F, W>::inputize(n, bits),
+        ]
+        .concat()
+    }
+
+    // Enforces c = Enc(m; r) with m, r < n. c only fixes m modulo n, as g^(m + n) = g^m, so
+    // without m < n, anything proven about m could be proven about m + n instead.
+    pub fn enforce_encryption(
+        &self,
+        c: &BigUintVar<F, W>,
+        m: &BigUintVar<F, W>,
+        r: &BigUintVar<F, W>,
+    ) -> Result<(), SynthesisError> {
+        m.enforce_lt(&self.n)?;
+        r.enforce_lt(&self.n)?;
+        PaillierEncGadget::encrypt(&self.g, &self.n, &self.nn, m, r, &pow2_bound(self.bits * 2))?
+            .enforce_equal_unaligned(c)
+    }
+}
+
+#[derive(Clone)]
+pub struct PaillierEncCircuit<const W: usize> {
+    pub bits: usize,
+    pub message_bits: usize,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+    pub enforce_coprime: bool,
+    // A precomputed g^m, see `from_gm`. With `check_gm`, it is a witness proven to be g^m.
+    // Otherwise nothing ties it to m, so it becomes a public input after c, and the statement
+    // is only that c = g^m * r^n for the g^m given by the verifier.
+    pub gm: Option<BigUint>,
+    pub check_gm: bool,
+    // The generator, n + 1 if unset. Any g whose order in Z*_{n^2} is a multiple of n works,
+    // but only g = n + 1 admits g^m = 1 + m * n (mod n^2). Which of the two is used is decided
+    // when building the circuit, so the setup has to use the same kind of g as the prover.
+    pub g: Option<BigUint>,
+    // Allocates the public inputs with `BigUintVar::new_input_packed`, so that the verifier has
+    // to encode them with `inputize_packed`.
+    pub pack_inputs: bool,
+    // Only makes n and c public, see `minimal_public_inputs`. n^2 is then a witness computed
+    // from n, and so is g if it is n + 1. Otherwise g stays public and comes first.
+    pub minimal_inputs: bool,
+    // How every product and remainder modulo n^2 is reduced.
+    pub reduction: ReductionStrategy,
+}
+
+impl<const W: usize> PaillierEncCircuit<W> {
+    pub fn new(bits: usize, m: BigUint, n: BigUint, r: BigUint, c: BigUint) -> Self {
+        Self {
+            bits,
+            message_bits: bits,
+            m,
+            n,
+            r,
+            c,
+            enforce_coprime: false,
+            gm: None,
+            check_gm: false,
+            g: None,
+            pack_inputs: false,
+            minimal_inputs: false,
+            reduction: ReductionStrategy::default(),
+        }
+    }
+
+    pub fn new_with_strategy(
+        bits: usize,
+        m: BigUint,
+        n: BigUint,
+        r: BigUint,
+        c: BigUint,
+        reduction: ReductionStrategy,
+    ) -> Self {
+        Self { reduction, ..Self::new(bits, m, n, r, c) }
+    }
+
+    pub fn with_g(bits: usize, g: BigUint, m: BigUint, n: BigUint, r: BigUint, c: BigUint) -> Self {
+        Self { g: Some(g), ..Self::new(bits, m, n, r, c) }
+    }
+
+    pub fn from_native(pk: &PublicKey, m: BigUint, r: BigUint) -> Self {
+        let c = encrypt_with_randomness(pk, &m, &r).0;
+        Self::new(pk.bits, m, pk.n.clone(), r, c)
+    }
+
+    pub fn from_gm(
+        bits: usize,
+        gm: BigUint,
+        m: BigUint,
+        n: BigUint,
+        r: BigUint,
+        c: BigUint,
+        check_gm: bool,
+    ) -> Self {
+        Self { gm: Some(gm), check_gm, ..Self::new(bits, m, n, r, c) }
+    }
+
+    // The public inputs with `minimal_inputs` and g = n + 1, where n^2 and g are not part of
+    // the statement as they follow from n. Packed inputs need `inputize_packed` instead.
+    pub fn minimal_public_inputs<F: PrimeField>(bits: usize, n: &BigUint, c: &BigUint) -> Vec<F> {
+        [BigUintVar::<F, W>::inputize(n, bits), BigUintVar::<F, W>::inputize(c, bits * 2)].concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PaillierEncCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let message_bits = self.message_bits;
+        let g = self.g.unwrap_or_else(|| &self.n + BigUint::one());
+        let g_is_default = g == &self.n + BigUint::one();
+        let m_var = if self.gm.is_none() || self.check_gm {
+            Some(BigUintVar::<F, W>::new_witness(cs.clone(), || {
+                if self.m.bits() as usize > message_bits {
+                    return Err(SynthesisError::Unsatisfiable);
+                }
+                Ok((self.m, message_bits))
+            })?)
+        } else {
+            None
+        };
+        let new_input = |x: BigUint, l: usize| {
+            if self.pack_inputs {
+                BigUintVar::<F, W>::new_input_packed(cs.clone(), || Ok((x, l)))
+            } else {
+                BigUintVar::<F, W>::new_input(cs.clone(), || Ok((x, l)))
+            }
+        };
+        let one = BigUintVar::constant(BigUint::one(), 1)?;
+        let (nn_var, g_var, n_var, c_var) = if self.minimal_inputs {
+            let nn = &self.n * &self.n;
+            let g_var = if g_is_default { None } else { Some(new_input(g, bits * 2)?) };
+            let n_var = new_input(self.n, bits)?;
+            let c_var = new_input(self.c, bits * 2)?;
+            let nn_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((nn, bits * 2)))?;
+            n_var.mul_no_carry(&n_var)?.enforce_equal_unaligned(&nn_var)?;
+            let g_var = g_var.unwrap_or_else(|| n_var.add_no_carry(&one));
+            (nn_var, g_var, n_var, c_var)
+        } else {
+            let nn_var = new_input(&self.n * &self.n, bits * 2)?;
+            let g_var = new_input(g, bits * 2)?;
+            let n_var = new_input(self.n, bits)?;
+            let c_var = new_input(self.c, bits * 2)?;
+            (nn_var, g_var, n_var, c_var)
+        };
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+        // Every reduction modulo n^2 shares this one, and so does the setup of its strategy.
+        let reducer = Reducer::new(&nn_var, &pow2_bound(bits * 2), self.reduction)?;
+        // For g = n + 1, g^m = 1 + m * n (mod n^2). The fast path is only sound if the public
+        // g is indeed n + 1.
+        let g_pow_m = |m_var: &BigUintVar<F, W>| {
+            let _ns = ns!(cs, "g_pow_m");
+            g_var.enforce_equal_unaligned(&n_var.add_no_carry(&one))?;
+            m_var.mul_no_carry(&n_var)?.add_no_carry(&one).rem_with(&reducer)
+        };
+
+        r_var.enforce_lt(&n_var)?;
+        if self.enforce_coprime {
+            r_var.enforce_coprime(&n_var)?;
+        }
+
+        match self.gm {
+            None if g_is_default => {
+                let gm_var = g_pow_m(&m_var.unwrap())?;
+                PaillierEncGadget::encrypt_with_gm_strategy(&n_var, &gm_var, &r_var, &reducer)?
+                    .enforce_equal_unaligned(&c_var)?;
+            }
+            None => PaillierEncGadget::encrypt_with_strategy(
+                &g_var,
+                &n_var,
+                &m_var.unwrap(),
+                &r_var,
+                &reducer,
+            )?
+            .enforce_equal_unaligned(&c_var)?,
+            Some(gm) => {
+                let gm_var = if self.check_gm {
+                    BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((gm, bits * 2)))?
+                } else {
+                    new_input(gm, bits * 2)?
+                };
+                if let Some(m_var) = m_var {
+                    if g_is_default {
+                        g_pow_m(&m_var)?.enforce_equal_unaligned(&gm_var)?;
+                    } else {
+                        g_var
+                            .clone()
+                            .powm_with(&m_var.to_bits_le_truncated(message_bits)?, &reducer)?
+                            .enforce_equal_unaligned(&gm_var)?;
+                    }
+                }
+                PaillierEncGadget::encrypt_with_gm_strategy(&n_var, &gm_var, &r_var, &reducer)?
+                    .enforce_equal_unaligned(&c_var)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cmp::min, error::Error};
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, BigInt, Integer, Zero};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{native::sample_randomness, reference};
+
+    const W: usize = 32;
+    const N: usize = crate::TEST_BITS;
+
+    #[test]
+    fn test_degenerate_encryptions() -> Result<(), Box<dyn Error>> {
+        const N: usize = 256;
+        let rng = &mut thread_rng();
+        let (pk, _, _) = reference::keygen(N, rng);
+        let (n, nn) = (pk.n.clone(), pk.nn());
+        let a = sample_randomness(&pk, rng);
+        let general = (BigUint::one() + &a * &n) % &nn;
+        let m = rng.gen_biguint_below(&n);
+        let r = sample_randomness(&pk, rng);
+
+        // c = r^n for m = 0, c = g^m for r = 1, and c = 1 for both.
+        for (m, r) in [
+            (BigUint::zero(), r.clone()),
+            (m.clone(), BigUint::one()),
+            (BigUint::zero(), BigUint::one()),
+        ] {
+            for g in [pk.g(), general.clone()] {
+                let gm = g.modpow(&m, &nn);
+                let c = &gm * r.modpow(&n, &nn) % &nn;
+                assert_eq!(c.is_one(), m.is_zero() && r.is_one());
+                let circuit = PaillierEncCircuit::<W>::with_g(
+                    N,
+                    g.clone(),
+                    m.clone(),
+                    n.clone(),
+                    r.clone(),
+                    c.clone(),
+                );
+                for circuit in [
+                    circuit.clone(),
+                    PaillierEncCircuit { enforce_coprime: true, ..circuit.clone() },
+                    PaillierEncCircuit { gm: Some(gm.clone()), check_gm: true, ..circuit.clone() },
+                ] {
+                    let cs = ConstraintSystem::<Fr>::new_ref();
+                    circuit.clone().generate_constraints(cs.clone())?;
+                    assert!(cs.is_satisfied()?);
+
+                    let cs = ConstraintSystem::<Fr>::new_ref();
+                    PaillierEncCircuit { c: (&c * &g) % &nn, ..circuit }
+                        .generate_constraints(cs.clone())?;
+                    assert!(!cs.is_satisfied()?);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_coprime() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let m = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+
+        for (r, enforce_coprime, satisfied) in [
+            (rng.gen_biguint_below(&n), true, true),
+            (&p * rng.gen_biguint_range(&BigUint::one(), &q), true, false),
+            (&p * rng.gen_biguint_range(&BigUint::one(), &q), false, true),
+        ] {
+            let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W> {
+                enforce_coprime,
+                ..PaillierEncCircuit::new(N, m.clone(), n.clone(), r, c)
+            }
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_enforce_valid_ciphertext() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, &p * &q);
+        let nn = pk.nn();
+
+        for (c, satisfied) in [
+            (
+                encrypt_with_randomness(
+                    &pk,
+                    &rng.gen_biguint_below(&pk.n),
+                    &sample_randomness(&pk, rng),
+                )
+                .0,
+                true,
+            ),
+            (&p * rng.gen_biguint_range(&BigUint::one(), &(&q * &pk.n)), false),
+            (nn.clone(), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let c_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((c, N * 2)))?;
+            let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((pk.n.clone(), N)))?;
+            let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+            enforce_valid_ciphertext(&c_var, &n_var, &nn_var)?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_gm() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let m = rng.gen_biguint_below(&n);
+        let r = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+        let gm = g.modpow(&m, &nn);
+        let c = (&gm * r.modpow(&n, &nn)) % &nn;
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        PaillierEncCircuit::<W>::new(N, m.clone(), n.clone(), r.clone(), c.clone())
+            .generate_constraints(cs.clone())?;
+        assert!(cs.is_satisfied()?);
+        let full = cs.num_constraints();
+
+        for check_gm in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W>::from_gm(
+                N,
+                gm.clone(),
+                m.clone(),
+                n.clone(),
+                r.clone(),
+                c.clone(),
+                check_gm,
+            )
+            .generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+            // Checking the hint costs about as much as the fast path for g = n + 1 itself.
+            if !check_gm {
+                assert!(cs.num_constraints() < full);
+            }
+        }
+
+        let gm = g.modpow(&(&m + BigUint::one()), &nn);
+        let c = (&gm * r.modpow(&n, &nn)) % &nn;
+        for check_gm in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W>::from_gm(
+                N,
+                gm.clone(),
+                m.clone(),
+                n.clone(),
+                r.clone(),
+                c.clone(),
+                check_gm,
+            )
+            .generate_constraints(cs.clone())?;
+            // Unchecked, the statement is about the public g^m, which c does encrypt.
+            assert_eq!(cs.is_satisfied()?, !check_gm);
+            // n^2, g, n and c, then g^m if unchecked.
+            let inputs = if check_gm { 7 * N / W } else { 9 * N / W };
+            assert_eq!(cs.num_instance_variables(), 1 + inputs);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_carmichael_lambda() -> Result<(), Box<dyn Error>> {
+        const N: usize = 256;
+        let rng = &mut thread_rng();
+        let (pk, p, q) = reference::keygen(N, rng);
+        let m = rng.gen_biguint_below(&pk.n);
+        let c = encrypt_with_randomness(&pk, &m, &sample_randomness(&pk, rng)).0;
+        let lambda = (&p - BigUint::one()).lcm(&(&q - BigUint::one()));
+        let n = BigInt::from(pk.n.clone());
+        let mu =
+            BigInt::from(lambda.clone()).extended_gcd(&n).x.mod_floor(&n).to_biguint().unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let nn_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((pk.nn(), N * 2)))?;
+        let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((pk.n.clone(), N)))?;
+        let c_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((c.clone(), N * 2)))?;
+        let p_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((p.clone(), N / 2)))?;
+        let q_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((q.clone(), N / 2)))?;
+        let mu_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((mu, N)))?;
+        p_var.mul_no_carry(&q_var)?.enforce_equal_unaligned(&n_var)?;
+
+        let lambda_bits = carmichael_lambda(&p_var, &q_var, N)?;
+        assert_eq!(lambda_bits.len(), N);
+        let u = c_var.powm(&lambda_bits, &nn_var, &pow2_bound(N * 2 - 2))?;
+        let m_var = BigUintVar::paillier_l(&u, &n_var)?
+            .mul_no_carry(&mu_var)?
+            .rem_reduced(&n_var, &pow2_bound(N - 1))?;
+        assert_eq!(m_var.value()?, reference::decrypt(&p, &q, &c));
+        assert_eq!(m_var.value()?, m);
+        assert!(cs.is_satisfied()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_g() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, &p * &q);
+        let (n, nn) = (pk.n.clone(), pk.nn());
+        let m = rng.gen_biguint_below(&n);
+        let r = sample_randomness(&pk, rng);
+
+        // (1 + a * n) has order n, so g = (1 + a * n) * b^n has order a multiple of n.
+        let a = sample_randomness(&pk, rng);
+        let b = sample_randomness(&pk, rng);
+        let general = (BigUint::one() + &a * &n) * b.modpow(&n, &nn) % &nn;
+
+        let mut counts = vec![];
+        for g in [pk.g(), general] {
+            let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+            let circuit =
+                PaillierEncCircuit::<W>::with_g(N, g.clone(), m.clone(), n.clone(), r.clone(), c);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+            counts.push(cs.num_constraints());
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W> { m: &m + BigUint::one(), ..circuit }
+                .generate_constraints(cs.clone())?;
+            assert!(!cs.is_satisfied()?);
+        }
+        assert!(counts[0] < counts[1]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reduction_strategies() -> Result<(), Box<dyn Error>> {
+        const N: usize = 256;
+        let rng = &mut thread_rng();
+        // A full-width n, as from `keygen`, is all that the width bound on n^2 needs.
+        let (pk, _, _) = reference::keygen(N, rng);
+        let (n, nn) = (pk.n.clone(), pk.nn());
+        let m = rng.gen_biguint_below(&n);
+        let r = sample_randomness(&pk, rng);
+        let a = sample_randomness(&pk, rng);
+        let general = (BigUint::one() + &a * &n) % &nn;
+
+        for g in [pk.g(), general] {
+            let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+            let mut counts = vec![];
+            for strategy in [
+                ReductionStrategy::Schoolbook,
+                ReductionStrategy::WidthBound,
+                ReductionStrategy::Montgomery,
+            ] {
+                let circuit = PaillierEncCircuit::<W> {
+                    g: Some(g.clone()),
+                    ..PaillierEncCircuit::new_with_strategy(
+                        N,
+                        m.clone(),
+                        n.clone(),
+                        r.clone(),
+                        c.clone(),
+                        strategy,
+                    )
+                };
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                circuit.clone().generate_constraints(cs.clone())?;
+                assert!(cs.is_satisfied()?, "{:?}", strategy);
+                let constraints = cs.num_constraints();
+
+                // The shape only depends on the strategy, not on whether c is right.
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                PaillierEncCircuit::<W> { c: (&c * &g) % &nn, ..circuit }
+                    .generate_constraints(cs.clone())?;
+                assert!(!cs.is_satisfied()?, "{:?}", strategy);
+                assert_eq!(cs.num_constraints(), constraints, "{:?}", strategy);
+                counts.push((strategy, constraints));
+
+                let cs = ConstraintSystem::<Fr>::new_ref();
+                let nn_var =
+                    BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((nn.clone(), N * 2)))?;
+                let g_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((g.clone(), N * 2)))?;
+                let n_var = BigUintVar::<Fr, W>::new_input(cs.clone(), || Ok((n.clone(), N)))?;
+                let m_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((m.clone(), N)))?;
+                let r_var = BigUintVar::<Fr, W>::new_witness(cs.clone(), || Ok((r.clone(), N)))?;
+                let reducer = Reducer::new(&nn_var, &pow2_bound(N * 2), strategy)?;
+                let c_var = PaillierEncGadget::encrypt_with_strategy(
+                    &g_var, &n_var, &m_var, &r_var, &reducer,
+                )?;
+                assert_eq!(c_var.value()?, c, "{:?}", strategy);
+                assert!(cs.is_satisfied()?, "{:?}", strategy);
+            }
+
+            for (strategy, constraints) in &counts {
+                println!("{:?}: {}", strategy, constraints);
+            }
+            // The width bound on n^2 is looser than the schoolbook bound passed by the circuit,
+            // so it never needs fewer constraints.
+            assert!(counts[0].1 <= counts[1].1, "{:?}", counts);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_r_too_large() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let m = rng.gen_biguint_below(&n);
+        let g = &n + BigUint::one();
+
+        for (r, satisfied) in [
+            (sample_randomness(&PublicKey::new(N, n.clone()), rng), true),
+            (&n - BigUint::one(), true),
+            (n.clone(), false),
+            (&n + sample_randomness(&PublicKey::new(N, n.clone()), rng), false),
+        ] {
+            let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W>::new(N, m.clone(), n.clone(), r, c)
+                .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_bits() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let n = &p * &q;
+        let nn = &n * &n;
+        let r = sample_randomness(&PublicKey::new(N, n.clone()), rng);
+        let g = &n + BigUint::one();
+
+        let mut counts = vec![];
+        for message_bits in [1, 64, N] {
+            let m = rng.gen_biguint_below(&min(BigUint::one() << message_bits, n.clone()));
+            let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W> {
+                message_bits,
+                ..PaillierEncCircuit::new(N, m, n.clone(), r.clone(), c)
+            }
+            .generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+            counts.push(cs.num_constraints());
+        }
+        assert!(counts.windows(2).all(|w| w[0] < w[1]));
+
+        let m = BigUint::one() << 64;
+        let c = (g.modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        assert!(matches!(
+            PaillierEncCircuit::<W> { message_bits: 64, ..PaillierEncCircuit::new(N, m, n, r, c) }
+                .generate_constraints(cs),
+            Err(SynthesisError::Unsatisfiable)
+        ));
+
+        Ok(())
+    }
+}
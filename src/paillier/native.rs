@@ -0,0 +1,86 @@
+use num::{bigint::RandBigInt, BigUint, Integer, One};
+use rand::{CryptoRng, RngCore};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PublicKey {
+    pub bits: usize,
+    pub n: BigUint,
+}
+
+impl PublicKey {
+    pub fn new(bits: usize, n: BigUint) -> Self {
+        assert!(n.bits() as usize <= bits, "n does not fit in {} bits", bits);
+        assert!(n.is_odd() && n > BigUint::one(), "n is not a valid Paillier modulus");
+        Self { bits, n }
+    }
+
+    pub fn nn(&self) -> BigUint {
+        &self.n * &self.n
+    }
+
+    pub fn g(&self) -> BigUint {
+        &self.n + BigUint::one()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Ciphertext(pub BigUint);
+
+pub fn sample_randomness<R: RngCore + CryptoRng>(pk: &PublicKey, rng: &mut R) -> BigUint {
+    loop {
+        let r = rng.gen_biguint_range(&BigUint::one(), &pk.n);
+        if r.gcd(&pk.n).is_one() {
+            return r;
+        }
+    }
+}
+
+// Computes c = g^m * r^n mod n^2 under the same conventions as `PaillierEncCircuit`: g = n + 1,
+// m < n, and r a unit below n.
+pub fn encrypt_with_randomness(pk: &PublicKey, m: &BigUint, r: &BigUint) -> Ciphertext {
+    assert!(m < &pk.n, "message is not below n");
+    assert!(r < &pk.n && r.gcd(&pk.n).is_one(), "randomness is not a unit below n");
+    let nn = pk.nn();
+    Ciphertext(pk.g().modpow(m, &nn) * r.modpow(&pk.n, &nn) % &nn)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::PaillierEncCircuit;
+
+    const W: usize = 32;
+    const N: usize = 128;
+
+    #[test]
+    fn test_encrypt_matches_circuit() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        for _ in 0..100 {
+            let p: BigUint = rng.gen_prime_exact(N / 2, None);
+            let q: BigUint = rng.gen_prime_exact(N / 2, None);
+            let pk = PublicKey::new(N, p * q);
+            let m = rng.gen_biguint_below(&pk.n);
+            let r = sample_randomness(&pk, rng);
+
+            let circuit = PaillierEncCircuit::<W>::from_native(&pk, m, r);
+            assert_eq!(circuit.c, encrypt_with_randomness(&pk, &circuit.m, &circuit.r).0);
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W> { c: (&circuit.c + BigUint::one()) % pk.nn(), ..circuit }
+                .generate_constraints(cs.clone())?;
+            assert!(!cs.is_satisfied()?);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::{groth16, PublicKeyVar};
+use crate::bn::BigUintVar;
+
+// Proves that Dec(c1) < Dec(c2) under the same n without revealing either plaintext, e.g., to
+// show that one sealed bid beats another.
+#[derive(Clone)]
+pub struct PlaintextOrderCircuit<const W: usize> {
+    pub bits: usize,
+    pub n: BigUint,
+    pub m1: BigUint,
+    pub r1: BigUint,
+    pub c1: BigUint,
+    pub m2: BigUint,
+    pub r2: BigUint,
+    pub c2: BigUint,
+}
+
+impl<const W: usize> PlaintextOrderCircuit<W> {
+    pub fn public_inputs<F: PrimeField>(
+        bits: usize,
+        n: &BigUint,
+        c1: &BigUint,
+        c2: &BigUint,
+    ) -> Vec<F> {
+        [groth16::public_inputs::<F, W>(bits, n, c1), BigUintVar::<F, W>::inputize(c2, bits * 2)]
+            .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PlaintextOrderCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let mut m_vars = vec![];
+        for (m, r, c) in [(self.m1, self.r1, self.c1), (self.m2, self.r2, self.c2)] {
+            let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((c, bits * 2)))?;
+            let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((m, bits)))?;
+            let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((r, bits)))?;
+            // The ciphertext only fixes m modulo n, so the comparison is only meaningful on
+            // the representative in [0, n), which this enforces. Otherwise, m + n would beat
+            // any m' > m.
+            key.enforce_encryption(&c_var, &m_var, &r_var)?;
+            m_vars.push(m_var);
+        }
+
+        m_vars[0].enforce_lt(&m_vars[1])?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One, Zero};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness, PublicKey},
+        reference::keygen,
+    };
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    fn circuit(pk: &PublicKey, m1: &BigUint, m2: &BigUint) -> PlaintextOrderCircuit<W> {
+        let rng = &mut thread_rng();
+        let (r1, r2) = (sample_randomness(pk, rng), sample_randomness(pk, rng));
+        PlaintextOrderCircuit {
+            bits: N,
+            n: pk.n.clone(),
+            c1: encrypt_with_randomness(pk, m1, &r1).0,
+            c2: encrypt_with_randomness(pk, m2, &r2).0,
+            m1: m1.clone(),
+            r1,
+            m2: m2.clone(),
+            r2,
+        }
+    }
+
+    #[test]
+    fn test_plaintext_order() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&(&pk.n - BigUint::one()));
+
+        let params = generate_random_parameters::<Bn254, _, _>(
+            circuit(&pk, &BigUint::zero(), &BigUint::one()),
+            rng,
+        )?;
+        let vk = prepare_verifying_key(&params.vk);
+        let ordered = circuit(&pk, &m, &(&m + BigUint::one()));
+        let (c1, c2) = (ordered.c1.clone(), ordered.c2.clone());
+        let pi = create_random_proof(ordered, &params, rng)?;
+        let inputs = |c1: &BigUint, c2: &BigUint| {
+            PlaintextOrderCircuit::<W>::public_inputs::<Fr>(N, &pk.n, c1, c2)
+        };
+        assert!(verify_proof(&vk, &pi, &inputs(&c1, &c2))?);
+        assert!(!verify_proof(&vk, &pi, &inputs(&c2, &c1))?);
+
+        for (m1, m2, satisfied) in [
+            (m.clone(), &m + BigUint::one(), true),
+            (BigUint::zero(), &pk.n - BigUint::one(), true),
+            (&m + BigUint::one(), m.clone(), false),
+            (m.clone(), m.clone(), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit(&pk, &m1, &m2).generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // 1 + n encrypts the same as 1 and still fits in N bits, but it is not below n.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        PlaintextOrderCircuit::<W> {
+            m2: &pk.n + BigUint::one(),
+            ..circuit(&pk, &BigUint::from(2u32), &BigUint::one())
+        }
+        .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    boolean::Boolean,
+    fields::{fp::FpVar, FieldVar},
+    prelude::EqGadget,
+    ToBitsGadget,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::{BigUint, One};
+
+use crate::bn::BigUintVar;
+
+// Encryption circuit whose shape only depends on `max_bits` and `min_bits`, so that keys of any
+// size between the two share a verifying key. The actual bit length of n is a public input.
+#[derive(Clone)]
+pub struct PaddedEncryptionCircuit<const W: usize> {
+    pub max_bits: usize,
+    pub min_bits: usize,
+    pub bits: usize,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+}
+
+impl<const W: usize> PaddedEncryptionCircuit<W> {
+    pub fn new(
+        max_bits: usize,
+        min_bits: usize,
+        bits: usize,
+        m: BigUint,
+        n: BigUint,
+        r: BigUint,
+        c: BigUint,
+    ) -> Self {
+        assert!(min_bits >= 1 && (min_bits..=max_bits).contains(&bits));
+        Self { max_bits, min_bits, bits, m, n, r, c }
+    }
+
+    pub fn public_inputs<F: PrimeField>(
+        max_bits: usize,
+        bits: usize,
+        n: &BigUint,
+        c: &BigUint,
+    ) -> Vec<F> {
+        [
+            vec![F::from(bits as u64)],
+            BigUintVar::<F, W>::inputize(&(n * n), max_bits * 2),
+            BigUintVar::<F, W>::inputize(&(n + BigUint::one()), max_bits * 2),
+            BigUintVar::<F, W>::inputize(n, max_bits),
+            BigUintVar::<F, W>::inputize(c, max_bits * 2),
+        ]
+        .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PaddedEncryptionCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let max_bits = self.max_bits;
+        let bits = self.bits;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, max_bits)))?;
+        let bits_var = FpVar::new_input(cs.clone(), || Ok(F::from(bits as u64)))?;
+        let nn_var =
+            BigUintVar::<F, W>::new_input(cs.clone(), || Ok((&self.n * &self.n, max_bits * 2)))?;
+        let g_var = BigUintVar::<F, W>::new_input(cs.clone(), || {
+            Ok((&self.n + BigUint::one(), max_bits * 2))
+        })?;
+        let n_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.n, max_bits)))?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, max_bits * 2)))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, max_bits * 2)))?;
+
+        // flags[i] is set iff i < bits: the flags are non-increasing and sum up to `bits`.
+        let flags = Vec::<Boolean<F>>::new_witness(cs.clone(), || {
+            Ok((0..max_bits).map(|i| i < bits).collect::<Vec<_>>())
+        })?;
+        for i in 1..max_bits {
+            flags[i - 1].conditional_enforce_equal(&Boolean::TRUE, &flags[i])?;
+        }
+        flags
+            .iter()
+            .fold(FpVar::zero(), |acc, f| acc + FpVar::from(f.clone()))
+            .enforce_equal(&bits_var)?;
+        flags[self.min_bits - 1].enforce_equal(&Boolean::TRUE)?;
+
+        let m_bits = m_var.to_bits_le()?;
+        let n_bits = n_var.to_bits_le()?;
+        for (i, (m, n)) in m_bits.iter().zip(&n_bits).enumerate() {
+            m.conditional_enforce_equal(&Boolean::FALSE, &flags[i].not())?;
+            n.conditional_enforce_equal(&Boolean::FALSE, &flags[i].not())?;
+            // Bit `bits - 1` of n, the last one whose flag is set, is the top one.
+            let top = match flags.get(i + 1) {
+                Some(next) => flags[i].and(&next.not())?,
+                None => flags[i].clone(),
+            };
+            n.conditional_enforce_equal(&Boolean::TRUE, &top)?;
+        }
+        for (j, r) in r_var.to_bits_le()?.iter().enumerate() {
+            r.conditional_enforce_equal(&Boolean::FALSE, &flags[j / 2].not())?;
+        }
+        // c needs no separate check: it equals the result of `multi_powm`, which is below
+        // n^2 < 2^(2 * bits).
+
+        // The padding of m and n only zero-extends the exponents at the top. Those bits are
+        // decomposed from the same limbs as the rest and are enforced to be zero above, so
+        // they leave the exponents unchanged, and the extra windows multiply by the table entry
+        // for 0, which is 1. Every rem is still taken modulo the actual n^2, whose lower bound
+        // follows from n having at least `min_bits` bits.
+        BigUintVar::multi_powm(
+            &[g_var, r_var],
+            &[m_bits, n_bits],
+            &nn_var,
+            &(BigUint::one() << (self.min_bits * 2 - 2)),
+        )?
+        .enforce_equal_unaligned(&c_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::bigint::RandBigInt;
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+
+    const W: usize = 32;
+
+    fn keygen(bits: usize) -> (BigUint, BigUint, BigUint, BigUint) {
+        let rng = &mut thread_rng();
+        let n = loop {
+            let p: BigUint = rng.gen_prime_exact(bits / 2, None);
+            let q: BigUint = rng.gen_prime_exact(bits / 2, None);
+            let n = p * q;
+            if n.bits() as usize == bits {
+                break n;
+            }
+        };
+        let nn = &n * &n;
+        let m = rng.gen_biguint_below(&n);
+        let r = rng.gen_biguint_below(&n);
+        let c = ((&n + BigUint::one()).modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+        (m, n, r, c)
+    }
+
+    fn test_padded(max_bits: usize, min_bits: usize) -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let pk = generate_random_parameters::<Bn254, _, _>(
+            PaddedEncryptionCircuit::<W>::new(
+                max_bits,
+                min_bits,
+                max_bits,
+                Default::default(),
+                rng.gen_biguint_range(
+                    &(BigUint::one() << (max_bits - 1)),
+                    &(BigUint::one() << max_bits),
+                ),
+                Default::default(),
+                Default::default(),
+            ),
+            rng,
+        )?;
+        let vk = prepare_verifying_key(&pk.vk);
+
+        for bits in [min_bits, max_bits] {
+            let (m, n, r, c) = keygen(bits);
+            let pi = create_random_proof(
+                PaddedEncryptionCircuit::<W>::new(
+                    max_bits,
+                    min_bits,
+                    bits,
+                    m,
+                    n.clone(),
+                    r,
+                    c.clone(),
+                ),
+                &pk,
+                rng,
+            )?;
+            assert!(verify_proof(
+                &vk,
+                &pi,
+                &PaddedEncryptionCircuit::<W>::public_inputs::<Fr>(max_bits, bits, &n, &c)
+            )?);
+            assert!(!verify_proof(
+                &vk,
+                &pi,
+                &PaddedEncryptionCircuit::<W>::public_inputs::<Fr>(max_bits, bits + 1, &n, &c)
+            )?);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_padded_small() -> Result<(), Box<dyn Error>> {
+        test_padded(256, 128)
+    }
+
+    #[test]
+    #[cfg(feature = "expensive-tests")]
+    fn test_padded_3072() -> Result<(), Box<dyn Error>> {
+        test_padded(3072, 1024)
+    }
+
+    #[test]
+    fn test_padding_enforced() -> Result<(), Box<dyn Error>> {
+        let (m, n, r, c) = keygen(128);
+        for (bits, satisfied) in [(128, true), (136, false), (127, false)] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaddedEncryptionCircuit::<W> {
+                bits,
+                ..PaddedEncryptionCircuit::new(
+                    256,
+                    64,
+                    128,
+                    m.clone(),
+                    n.clone(),
+                    r.clone(),
+                    c.clone(),
+                )
+            }
+            .generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        let nn = &n * &n;
+        let r = &r + (BigUint::one() << 256);
+        let c = ((&n + BigUint::one()).modpow(&m, &nn) * r.modpow(&n, &nn)) % &nn;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        PaddedEncryptionCircuit::<W>::new(256, 64, 128, m, n, r, c)
+            .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use ark_crypto_primitives::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{constraints::PoseidonSpongeVar, PoseidonSponge},
+    CryptographicSponge,
+};
+use ark_ff::{BigInteger, PrimeField};
+use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, prelude::EqGadget, ToBitsGadget};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::{
+    hash::poseidon_config,
+    native::{encrypt_with_randomness, PublicKey},
+    PublicKeyVar,
+};
+use crate::bn::{BigUintVar, BitsVar};
+
+// Convention for deriving r from a key k and a `bits`-bit message m:
+//   1. absorb k, then the W-bit limbs of m, into a fresh Poseidon sponge;
+//   2. squeeze t = ceil((bits + 128) / F::MODULUS_BIT_SIZE) field elements e_0, ..., e_{t-1};
+//   3. concatenate their canonical little-endian bit representations, i.e., take
+//      x = sum_i e_i * 2^(i * F::MODULUS_BIT_SIZE), which has at least 128 more bits than n;
+//   4. set r = x mod n.
+// The key is committed to as Poseidon(k), absorbing k alone.
+fn prf_elements<F: PrimeField>(bits: usize) -> usize {
+    (bits + 128).div_ceil(F::MODULUS_BIT_SIZE as usize)
+}
+
+pub fn commit_key<F: PrimeField>(key: F) -> F {
+    let mut sponge = PoseidonSponge::new(&poseidon_config());
+    sponge.absorb(&key);
+    sponge.squeeze_field_elements(1)[0]
+}
+
+pub fn prf_randomness<F: PrimeField, const W: usize>(
+    pk: &PublicKey,
+    key: F,
+    m: &BigUint,
+) -> BigUint {
+    let mut sponge = PoseidonSponge::new(&poseidon_config());
+    sponge.absorb(&key);
+    sponge.absorb(&BigUintVar::<F, W>::inputize(m, pk.bits));
+    let x = sponge.squeeze_field_elements::<F>(prf_elements::<F>(pk.bits)).iter().rev().fold(
+        BigUint::from(0u32),
+        |acc, e| {
+            (acc << F::MODULUS_BIT_SIZE) + BigUint::from_bytes_le(&e.into_bigint().to_bytes_le())
+        },
+    );
+    x % &pk.n
+}
+
+// Encryption of m under randomness derived from a committed key, see above.
+#[derive(Clone)]
+pub struct PrfEncryptionCircuit<F: PrimeField, const W: usize> {
+    pub bits: usize,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub c: BigUint,
+    pub key: F,
+    pub key_commitment: F,
+}
+
+impl<F: PrimeField, const W: usize> PrfEncryptionCircuit<F, W> {
+    pub fn new(pk: &PublicKey, m: BigUint, key: F) -> Self {
+        let c = encrypt_with_randomness(pk, &m, &prf_randomness::<F, W>(pk, key, &m)).0;
+        Self { bits: pk.bits, m, n: pk.n.clone(), c, key, key_commitment: commit_key(key) }
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PrfEncryptionCircuit<F, W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, bits)))?;
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits * 2)))?;
+        let key_commitment_var = FpVar::new_input(cs.clone(), || Ok(self.key_commitment))?;
+        let key_var = FpVar::new_witness(cs.clone(), || Ok(self.key))?;
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &poseidon_config());
+        sponge.absorb(&key_var)?;
+        sponge.squeeze_field_elements(1)?[0].enforce_equal(&key_commitment_var)?;
+
+        let mut sponge = PoseidonSpongeVar::new(cs.clone(), &poseidon_config());
+        sponge.absorb(&key_var)?;
+        sponge.absorb(&m_var.0.iter().map(|limb| limb.0.clone()).collect::<Vec<_>>())?;
+        let x_bits = sponge
+            .squeeze_field_elements(prf_elements::<F>(bits))?
+            .iter()
+            .map(|e| e.to_bits_le())
+            .collect::<Result<Vec<_>, _>>()?
+            .concat();
+        let x_var = BigUintVar::<F, W>(x_bits.chunks(W).map(BitsVar::from).collect());
+        let r_var = x_var.rem_auto(&key.n)?;
+
+        key.enforce_encryption(&c_var, &m_var, &r_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::One;
+    use num_prime::RandPrime;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_prf_encryption() -> Result<(), Box<dyn Error>> {
+        let rng = &mut StdRng::seed_from_u64(0);
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, p * q);
+        let key = Fr::from(42u64);
+        let m = BigUint::from(1234567890u64);
+
+        let circuit = PrfEncryptionCircuit::<Fr, W>::new(&pk, m.clone(), key);
+        assert_eq!(circuit.c, PrfEncryptionCircuit::<Fr, W>::new(&pk, m.clone(), key).c);
+        assert_ne!(
+            circuit.c,
+            PrfEncryptionCircuit::<Fr, W>::new(&pk, m.clone(), key + Fr::one()).c
+        );
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone())?;
+        assert!(cs.is_satisfied()?);
+
+        // Same message, but a ciphertext formed with other randomness.
+        let r = prf_randomness::<Fr, W>(&pk, key, &(&m + BigUint::one()));
+        let c = encrypt_with_randomness(&pk, &m, &r).0;
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        PrfEncryptionCircuit { c, ..circuit.clone() }.generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        // The right randomness, but a key that does not match the commitment.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        PrfEncryptionCircuit { key_commitment: commit_key(key + Fr::one()), ..circuit }
+            .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
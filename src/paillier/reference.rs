@@ -0,0 +1,77 @@
+use num::{BigInt, BigUint, Integer, One};
+use num_prime::RandPrime;
+use rand::{CryptoRng, RngCore};
+
+use super::native::{encrypt_with_randomness, sample_randomness, PublicKey};
+
+// Native reference for what the gadgets enforce: g = n + 1, c = g^m * r^n mod n^2 with
+// 0 < r < n a unit, and every result fully reduced.
+
+// Samples primes p and q such that n = p * q has exactly `n_bits` bits.
+pub fn keygen<R: RngCore + CryptoRng>(n_bits: usize, rng: &mut R) -> (PublicKey, BigUint, BigUint) {
+    loop {
+        let p: BigUint = rng.gen_prime_exact(n_bits / 2, None);
+        let q: BigUint = rng.gen_prime_exact(n_bits - n_bits / 2, None);
+        let n = &p * &q;
+        if p != q && n.bits() as usize == n_bits {
+            return (PublicKey::new(n_bits, n), p, q);
+        }
+    }
+}
+
+pub fn decrypt(p: &BigUint, q: &BigUint, c: &BigUint) -> BigUint {
+    let n = p * q;
+    let lambda = (p - BigUint::one()).lcm(&(q - BigUint::one()));
+    let l = (c.modpow(&lambda, &(&n * &n)) - BigUint::one()) / &n;
+    let n_int = BigInt::from(n.clone());
+    let mu = BigInt::from(lambda).extended_gcd(&n_int).x.mod_floor(&n_int);
+    l * mu.to_biguint().unwrap() % n
+}
+
+// Returns (n, c, r) for a fresh `n_bits`-bit key and c = Enc(m; r).
+pub fn paillier_keygen_and_encrypt<R: RngCore + CryptoRng>(
+    n_bits: usize,
+    m: &BigUint,
+    rng: &mut R,
+) -> (BigUint, BigUint, BigUint) {
+    let (pk, _, _) = keygen(n_bits, rng);
+    let r = sample_randomness(&pk, rng);
+    let c = encrypt_with_randomness(&pk, m, &r).0;
+    (pk.n, c, r)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystem};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::paillier::PaillierEncCircuit;
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_keygen_and_encrypt() -> Result<(), Box<dyn Error>> {
+        for seed in 0..4 {
+            let m = BigUint::from(1234567u32) * (seed + 1);
+            let (n, c, r) = paillier_keygen_and_encrypt(N, &m, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(n.bits() as usize, N);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierEncCircuit::<W>::new(N, m.clone(), n.clone(), r, c.clone())
+                .generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+
+            // The same seed gives the same key, whose factors decrypt c.
+            let (pk, p, q) = keygen(N, &mut StdRng::seed_from_u64(seed));
+            assert_eq!(pk.n, n);
+            assert_eq!(decrypt(&p, &q, &c), m);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,252 @@
+use std::cmp::min;
+
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::{
+    groth16,
+    native::{encrypt_with_randomness, PublicKey},
+    PublicKeyVar,
+};
+use crate::bn::BigUintVar;
+
+// Proves that c1 under n1 and c2 under n2 encrypt the same m. The moduli may differ in size.
+#[derive(Clone)]
+pub struct SamePlaintextTwoKeysCircuit<const W: usize> {
+    pub bits1: usize,
+    pub bits2: usize,
+    pub m: BigUint,
+    pub n1: BigUint,
+    pub r1: BigUint,
+    pub c1: BigUint,
+    pub n2: BigUint,
+    pub r2: BigUint,
+    pub c2: BigUint,
+}
+
+impl<const W: usize> SamePlaintextTwoKeysCircuit<W> {
+    pub fn public_inputs<F: PrimeField>(
+        bits1: usize,
+        bits2: usize,
+        n1: &BigUint,
+        c1: &BigUint,
+        n2: &BigUint,
+        c2: &BigUint,
+    ) -> Vec<F> {
+        [
+            groth16::public_inputs::<F, W>(bits1, n1, c1),
+            groth16::public_inputs::<F, W>(bits2, n2, c2),
+        ]
+        .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for SamePlaintextTwoKeysCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || {
+            Ok((self.m, min(self.bits1, self.bits2)))
+        })?;
+        for (bits, n, r, c) in
+            [(self.bits1, self.n1, self.r1, self.c1), (self.bits2, self.n2, self.r2, self.c2)]
+        {
+            let key = PublicKeyVar::new_input(cs.clone(), bits, n)?;
+            let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((c, bits * 2)))?;
+            let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((r, bits)))?;
+
+            // m is only well defined modulo each n, so it has to be below both of them, i.e.,
+            // below min(n1, n2), as each encryption enforces. The width of m alone does not
+            // imply this.
+            key.enforce_encryption(&c_var, &m_var, &r_var)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Proves that c2 under n2 re-encrypts c1 under n1, e.g., for a mix server that switches a
+// ciphertext to the next key. Both ciphertexts are public, and the prover knows m together
+// with the randomness of both. The statement is that of `SamePlaintextTwoKeysCircuit`, with
+// the same public inputs.
+#[derive(Clone)]
+pub struct KeySwitchCircuit<const W: usize>(pub SamePlaintextTwoKeysCircuit<W>);
+
+impl<const W: usize> KeySwitchCircuit<W> {
+    // Switches c1 = Enc_{n1}(m; r1) to Enc_{n2}(m; r2).
+    pub fn new(
+        pk1: &PublicKey,
+        c1: BigUint,
+        pk2: &PublicKey,
+        m: BigUint,
+        r1: BigUint,
+        r2: BigUint,
+    ) -> Self {
+        let c2 = encrypt_with_randomness(pk2, &m, &r2).0;
+        Self(SamePlaintextTwoKeysCircuit {
+            bits1: pk1.bits,
+            bits2: pk2.bits,
+            m,
+            n1: pk1.n.clone(),
+            r1,
+            c1,
+            n2: pk2.n.clone(),
+            r2,
+            c2,
+        })
+    }
+
+    pub fn c2(&self) -> &BigUint {
+        &self.0.c2
+    }
+
+    pub fn public_inputs<F: PrimeField>(
+        pk1: &PublicKey,
+        c1: &BigUint,
+        pk2: &PublicKey,
+        c2: &BigUint,
+    ) -> Vec<F> {
+        SamePlaintextTwoKeysCircuit::<W>::public_inputs(pk1.bits, pk2.bits, &pk1.n, c1, &pk2.n, c2)
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for KeySwitchCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        self.0.generate_constraints(cs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One};
+    use num_prime::RandPrime;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::native::{encrypt_with_randomness, sample_randomness, PublicKey};
+
+    const W: usize = 32;
+
+    fn keygen(bits: usize) -> PublicKey {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(bits / 2, None);
+        let q: BigUint = rng.gen_prime_exact(bits / 2, None);
+        PublicKey::new(bits, p * q)
+    }
+
+    fn circuit(
+        pk1: &PublicKey,
+        m1: &BigUint,
+        pk2: &PublicKey,
+        m2: &BigUint,
+    ) -> SamePlaintextTwoKeysCircuit<W> {
+        let rng = &mut thread_rng();
+        let (r1, r2) = (sample_randomness(pk1, rng), sample_randomness(pk2, rng));
+        SamePlaintextTwoKeysCircuit {
+            bits1: pk1.bits,
+            bits2: pk2.bits,
+            m: m1.clone(),
+            n1: pk1.n.clone(),
+            c1: encrypt_with_randomness(pk1, m1, &r1).0,
+            r1,
+            n2: pk2.n.clone(),
+            c2: encrypt_with_randomness(pk2, m2, &r2).0,
+            r2,
+        }
+    }
+
+    #[test]
+    fn test_same_plaintext_groth16() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk1, pk2) = (keygen(128), keygen(256));
+        let m = rng.gen_biguint_below(&pk1.n);
+
+        let params = generate_random_parameters::<Bn254, _, _>(circuit(&pk1, &m, &pk2, &m), rng)?;
+        let vk = prepare_verifying_key(&params.vk);
+        let circuit = circuit(&pk1, &m, &pk2, &m);
+        let (c1, c2) = (circuit.c1.clone(), circuit.c2.clone());
+        let pi = create_random_proof(circuit, &params, rng)?;
+        let inputs = |c2: &BigUint| {
+            SamePlaintextTwoKeysCircuit::<W>::public_inputs::<Fr>(128, 256, &pk1.n, &c1, &pk2.n, c2)
+        };
+        assert!(verify_proof(&vk, &pi, &inputs(&c2))?);
+        assert!(!verify_proof(&vk, &pi, &inputs(&(&c2 + BigUint::one())))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_same_plaintext_negative() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk1, pk2) = (keygen(256), keygen(128));
+        let m = rng.gen_biguint_below(&pk2.n);
+
+        for (m1, m2, satisfied) in [
+            (m.clone(), m.clone(), true),
+            (m.clone(), &m + BigUint::one(), false),
+            (&m + BigUint::one(), m.clone(), false),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit(&pk1, &m1, &pk2, &m2).generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // n2 + 1 also encrypts to 1 under n2 and fits in 128 bits, but it is not below n2.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit(&pk1, &(&pk2.n + BigUint::one()), &pk2, &BigUint::one())
+            .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_switch() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk1, pk2) = (keygen(128), keygen(256));
+        let m = rng.gen_biguint_below(&pk1.n);
+        let r1 = sample_randomness(&pk1, rng);
+        let c1 = encrypt_with_randomness(&pk1, &m, &r1).0;
+        let switch = |m: &BigUint| {
+            KeySwitchCircuit::<W>::new(
+                &pk1,
+                c1.clone(),
+                &pk2,
+                m.clone(),
+                r1.clone(),
+                sample_randomness(&pk2, &mut thread_rng()),
+            )
+        };
+
+        let params = generate_random_parameters::<Bn254, _, _>(switch(&m), rng)?;
+        let vk = prepare_verifying_key(&params.vk);
+        let circuit = switch(&m);
+        let c2 = circuit.c2().clone();
+        let pi = create_random_proof(circuit, &params, rng)?;
+        let inputs = |c2: &BigUint| KeySwitchCircuit::<W>::public_inputs::<Fr>(&pk1, &c1, &pk2, c2);
+        assert!(verify_proof(&vk, &pi, &inputs(&c2))?);
+        // Any other ciphertext of m under n2 is a different switch.
+        let c2_other = switch(&m).c2().clone();
+        assert!(!verify_proof(&vk, &pi, &inputs(&c2_other))?);
+
+        // A switch to m + 1 cannot claim the plaintext of c1.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let KeySwitchCircuit(changed) = switch(&(&m + BigUint::one()));
+        KeySwitchCircuit::<W>(SamePlaintextTwoKeysCircuit { m: m.clone(), ..changed })
+            .generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        switch(&m).generate_constraints(cs.clone())?;
+        assert!(cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
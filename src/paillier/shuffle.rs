@@ -0,0 +1,174 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::AllocVar,
+    boolean::Boolean,
+    fields::{fp::FpVar, FieldVar},
+    prelude::EqGadget,
+    select::CondSelectGadget,
+    ToBitsGadget,
+};
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use crate::bn::{pow2_bound, BigUintVar, BigUintVarArray};
+
+// Proves that `outputs` is a permutation of re-randomized `inputs`, i.e., outputs[i] =
+// inputs[perm[i]] * s_i^n mod n^2 for a secret permutation `perm` and secret s_i.
+#[derive(Clone)]
+pub struct ShuffleCircuit<const W: usize, const K: usize> {
+    pub bits: usize,
+    pub n: BigUint,
+    pub inputs: [BigUint; K],
+    pub outputs: [BigUint; K],
+    pub perm: [usize; K],
+    pub s: [BigUint; K],
+}
+
+impl<const W: usize, const K: usize> ShuffleCircuit<W, K> {
+    pub fn public_inputs<F: PrimeField>(&self) -> Vec<F> {
+        let bits = self.bits;
+        [
+            vec![
+                BigUintVar::<F, W>::inputize(&(&self.n * &self.n), bits * 2),
+                BigUintVar::<F, W>::inputize(&self.n, bits),
+            ],
+            self.inputs.iter().map(|c| BigUintVar::<F, W>::inputize(c, bits * 2)).collect(),
+            self.outputs.iter().map(|c| BigUintVar::<F, W>::inputize(c, bits * 2)).collect(),
+        ]
+        .concat()
+        .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize, const K: usize> ConstraintSynthesizer<F>
+    for ShuffleCircuit<W, K>
+{
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let nn_var =
+            BigUintVar::<F, W>::new_input(cs.clone(), || Ok((&self.n * &self.n, bits * 2)))?;
+        let n_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.n, bits)))?;
+        let BigUintVarArray(input_vars) =
+            BigUintVarArray::<F, W, K>::new_input(cs.clone(), || Ok((self.inputs, bits * 2)))?;
+        let BigUintVarArray(output_vars) =
+            BigUintVarArray::<F, W, K>::new_input(cs.clone(), || Ok((self.outputs, bits * 2)))?;
+        let BigUintVarArray(s_vars) =
+            BigUintVarArray::<F, W, K>::new_witness(cs.clone(), || Ok((self.s, bits)))?;
+        // selection[i][j] is set iff perm[i] = j.
+        let selection = self
+            .perm
+            .iter()
+            .map(|&p| {
+                Vec::<Boolean<F>>::new_witness(cs.clone(), || {
+                    Ok((0..K).map(|j| j == p).collect::<Vec<_>>())
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Every row and every column has exactly one set entry, so the selection is a
+        // permutation matrix.
+        for i in 0..K {
+            let row =
+                selection[i].iter().fold(FpVar::zero(), |acc, b| acc + FpVar::from(b.clone()));
+            row.enforce_equal(&FpVar::one())?;
+            let column =
+                selection.iter().fold(FpVar::zero(), |acc, row| acc + FpVar::from(row[i].clone()));
+            column.enforce_equal(&FpVar::one())?;
+        }
+
+        // All re-randomizations share n^2, the bits of n and the lower bound on n^2.
+        let n_bits = n_var.to_bits_le()?;
+        let nn_lbound = pow2_bound(bits * 2);
+        for ((row, s_var), output_var) in selection.iter().zip(s_vars).zip(&output_vars) {
+            // s = 0 would turn the output into 0, which is not a ciphertext.
+            s_var.sub_one()?;
+            s_var.enforce_lt(&n_var)?;
+            let selected = (1..K).try_fold(input_vars[0].clone(), |acc, j| {
+                BigUintVar::conditionally_select(&row[j], &input_vars[j], &acc)
+            })?;
+            let c = s_var
+                .powm(&n_bits, &nn_var, &nn_lbound)?
+                .mul_no_carry(&selected)?
+                .rem(&nn_var, &nn_lbound)?;
+            c.enforce_lt(&nn_var)?;
+            c.enforce_equal_unaligned(output_var)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{bigint::RandBigInt, One, Zero};
+    use num_prime::RandPrime;
+    use rand::{seq::SliceRandom, thread_rng};
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness, PublicKey},
+        reference::decrypt,
+    };
+
+    const W: usize = 32;
+    const N: usize = crate::TEST_BITS;
+    const K: usize = 4;
+
+    fn shuffle(pk: &PublicKey, inputs: &[BigUint; K], perm: [usize; K]) -> ShuffleCircuit<W, K> {
+        let rng = &mut thread_rng();
+        let nn = pk.nn();
+        let s: [BigUint; K] = std::array::from_fn(|_| sample_randomness(pk, rng));
+        let outputs = std::array::from_fn(|i| &inputs[perm[i]] * s[i].modpow(&pk.n, &nn) % &nn);
+        ShuffleCircuit { bits: N, n: pk.n.clone(), inputs: inputs.clone(), outputs, perm, s }
+    }
+
+    #[test]
+    fn test_shuffle() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let p: BigUint = rng.gen_prime_exact(N / 2, None);
+        let q: BigUint = rng.gen_prime_exact(N / 2, None);
+        let pk = PublicKey::new(N, &p * &q);
+        let ms: [BigUint; K] = std::array::from_fn(|_| rng.gen_biguint_below(&pk.n));
+        let inputs = std::array::from_fn(|i| {
+            encrypt_with_randomness(&pk, &ms[i], &sample_randomness(&pk, rng)).0
+        });
+        let mut perm: [usize; K] = std::array::from_fn(|i| i);
+        perm.shuffle(rng);
+
+        let circuit = shuffle(&pk, &inputs, perm);
+        for (i, c) in circuit.outputs.iter().enumerate() {
+            assert_eq!(decrypt(&p, &q, c), ms[perm[i]]);
+        }
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        circuit.clone().generate_constraints(cs.clone())?;
+        assert!(cs.is_satisfied()?);
+
+        // s = 0 re-randomizes any input to 0.
+        let mut s = circuit.s.clone();
+        let mut outputs = circuit.outputs.clone();
+        s[0] = BigUint::zero();
+        outputs[0] = BigUint::zero();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ShuffleCircuit { s, outputs, ..circuit.clone() }.generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        // A wrong output.
+        let mut outputs = circuit.outputs.clone();
+        outputs[0] += BigUint::one();
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ShuffleCircuit { outputs, ..circuit }.generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        // Duplicating an input is not a permutation, even though every output is a valid
+        // re-randomization of some input.
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        shuffle(&pk, &inputs, [0, 0, 1, 2]).generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::{BigInt, BigUint, Integer, One, Zero};
+
+use super::group::{ModularGroup, PaillierGroup};
+use crate::bn::{pow2_bound, BigUintVar};
+
+// Proves that c = c1 * c2^-1 mod n^2, i.e., that c encrypts m1 - m2 mod n. The subtraction
+// wraps around n exactly like the plaintexts do, so m1 < m2 needs no special treatment and
+// yields m1 - m2 + n. All of n, c1, c2 and c are public, and the inverse is the only witness.
+#[derive(Clone)]
+pub struct PaillierSubCircuit<const W: usize> {
+    pub bits: usize,
+    pub n: BigUint,
+    pub c1: BigUint,
+    pub c2: BigUint,
+    pub c: BigUint,
+}
+
+impl<const W: usize> PaillierSubCircuit<W> {
+    pub fn new(bits: usize, n: BigUint, c1: BigUint, c2: BigUint) -> Self {
+        let c = Self::output(&n, &c1, &c2);
+        Self { bits, n, c1, c2, c }
+    }
+
+    // Zero if c2 is not invertible, which no proof can satisfy.
+    pub fn output(n: &BigUint, c1: &BigUint, c2: &BigUint) -> BigUint {
+        let nn = BigInt::from(n * n);
+        let gcd = BigInt::from(c2.clone()).extended_gcd(&nn);
+        if !gcd.gcd.is_one() {
+            return BigUint::zero();
+        }
+        (BigInt::from(c1.clone()) * gcd.x).mod_floor(&nn).to_biguint().unwrap()
+    }
+
+    pub fn public_inputs<F: PrimeField>(
+        bits: usize,
+        n: &BigUint,
+        c1: &BigUint,
+        c2: &BigUint,
+        c: &BigUint,
+    ) -> Vec<F> {
+        [
+            BigUintVar::<F, W>::inputize(&(n * n), bits * 2),
+            BigUintVar::<F, W>::inputize(c1, bits * 2),
+            BigUintVar::<F, W>::inputize(c2, bits * 2),
+            BigUintVar::<F, W>::inputize(c, bits * 2),
+        ]
+        .concat()
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for PaillierSubCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits * 2;
+        let nn_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((&self.n * &self.n, bits)))?;
+        let c1_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c1, bits)))?;
+        let c2_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c2, bits)))?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits)))?;
+
+        let group = PaillierGroup::new(nn_var, pow2_bound(bits));
+        group.mul(&c1_var, &group.inverse(&c2_var)?)?.enforce_equal_unaligned(&c_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::Fr;
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::bigint::RandBigInt;
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness},
+        reference::{decrypt, keygen},
+    };
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_sub() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, p, q) = keygen(N, rng);
+        let m = rng.gen_biguint_below(&pk.n);
+        for (m1, m2) in [
+            (m.clone(), BigUint::zero()),
+            (m.clone(), m.clone()),
+            (m.clone(), &m + BigUint::one()),
+            (BigUint::zero(), &pk.n - BigUint::one()),
+            (rng.gen_biguint_below(&pk.n), rng.gen_biguint_below(&pk.n)),
+        ] {
+            let c1 = encrypt_with_randomness(&pk, &m1, &sample_randomness(&pk, rng)).0;
+            let c2 = encrypt_with_randomness(&pk, &m2, &sample_randomness(&pk, rng)).0;
+            let circuit = PaillierSubCircuit::<W>::new(N, pk.n.clone(), c1, c2);
+            assert_eq!(decrypt(&p, &q, &circuit.c), (&m1 + &pk.n - &m2) % &pk.n);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit.clone().generate_constraints(cs.clone())?;
+            assert!(cs.is_satisfied()?);
+
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            PaillierSubCircuit::<W> { c: &circuit.c1 * &circuit.c2 % pk.nn(), ..circuit }
+                .generate_constraints(cs.clone())?;
+            assert!(!cs.is_satisfied()?);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,107 @@
+use ark_ff::PrimeField;
+use ark_r1cs_std::alloc::AllocVar;
+use ark_relations::r1cs::{ConstraintSynthesizer, ConstraintSystemRef, SynthesisError};
+use num::BigUint;
+
+use super::{groth16, PublicKeyVar};
+use crate::bn::BigUintVar;
+
+// Proves that c encrypts some m >= t, e.g., that an encrypted age is at least 18. As in
+// `IntervalPlaintextCircuit`, t is a constant of the circuit.
+#[derive(Clone)]
+pub struct ThresholdCircuit<const W: usize> {
+    pub bits: usize,
+    pub t: BigUint,
+    pub m: BigUint,
+    pub n: BigUint,
+    pub r: BigUint,
+    pub c: BigUint,
+}
+
+impl<const W: usize> ThresholdCircuit<W> {
+    pub fn new(bits: usize, t: BigUint, m: BigUint, n: BigUint, r: BigUint, c: BigUint) -> Self {
+        assert!(t.bits() as usize <= bits);
+        Self { bits, t, m, n, r, c }
+    }
+
+    pub fn public_inputs<F: PrimeField>(bits: usize, n: &BigUint, c: &BigUint) -> Vec<F> {
+        groth16::public_inputs::<F, W>(bits, n, c)
+    }
+}
+
+impl<F: PrimeField, const W: usize> ConstraintSynthesizer<F> for ThresholdCircuit<W> {
+    fn generate_constraints(self, cs: ConstraintSystemRef<F>) -> Result<(), SynthesisError> {
+        let bits = self.bits;
+        let m_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.m, bits)))?;
+        let key = PublicKeyVar::new_input(cs.clone(), bits, self.n)?;
+        let c_var = BigUintVar::<F, W>::new_input(cs.clone(), || Ok((self.c, bits * 2)))?;
+        let r_var = BigUintVar::<F, W>::new_witness(cs.clone(), || Ok((self.r, bits)))?;
+
+        m_var.enforce_ge(&BigUintVar::constant(self.t, bits)?)?;
+        // This includes m < n, without which the prover could add n to any plaintext below t.
+        key.enforce_encryption(&c_var, &m_var, &r_var)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::error::Error;
+
+    use ark_bn254::{Bn254, Fr};
+    use ark_groth16::{
+        create_random_proof, generate_random_parameters, prepare_verifying_key, verify_proof,
+    };
+    use ark_relations::r1cs::ConstraintSystem;
+    use num::{One, Zero};
+    use rand::thread_rng;
+
+    use super::*;
+    use crate::paillier::{
+        native::{encrypt_with_randomness, sample_randomness},
+        reference::keygen,
+    };
+
+    const W: usize = 32;
+    const N: usize = 256;
+
+    #[test]
+    fn test_threshold() -> Result<(), Box<dyn Error>> {
+        let rng = &mut thread_rng();
+        let (pk, _, _) = keygen(N, rng);
+        let t = BigUint::from(18u32);
+        let circuit = |m: &BigUint| {
+            let r = sample_randomness(&pk, &mut thread_rng());
+            let c = encrypt_with_randomness(&pk, m, &r).0;
+            ThresholdCircuit::<W>::new(N, t.clone(), m.clone(), pk.n.clone(), r, c)
+        };
+
+        let params = generate_random_parameters::<Bn254, _, _>(circuit(&t), rng)?;
+        let vk = prepare_verifying_key(&params.vk);
+        let adult = circuit(&BigUint::from(42u32));
+        let c = adult.c.clone();
+        let pi = create_random_proof(adult, &params, rng)?;
+        assert!(verify_proof(&vk, &pi, &ThresholdCircuit::<W>::public_inputs(N, &pk.n, &c))?);
+
+        for (m, satisfied) in [
+            (BigUint::zero(), false),
+            (&t - BigUint::one(), false),
+            (t.clone(), true),
+            (&t + BigUint::one(), true),
+            (&pk.n - BigUint::one(), true),
+        ] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            circuit(&m).generate_constraints(cs.clone())?;
+            assert_eq!(cs.is_satisfied()?, satisfied);
+        }
+
+        // 17 + n encrypts the same as 17 and is above t, but it is not below n.
+        let minor = circuit(&(&t - BigUint::one()));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        ThresholdCircuit::<W> { m: &minor.m + &pk.n, ..minor }.generate_constraints(cs.clone())?;
+        assert!(!cs.is_satisfied()?);
+
+        Ok(())
+    }
+}